@@ -1,8 +1,21 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program;
+use payout_vault::program::PayoutVault;
+use payout_vault::{LockWagerParams, SettlePayoutParams, VoidReason, WagerMode};
 
 declare_id!("TODO_REPLACE_WITH_PROGRAM_ID");
 
 pub const MAX_METADATA_URI_LEN: usize = 200;
+pub const MAX_BEST_OF: u8 = 9;
+pub const DEFAULT_RATING: u32 = 1200;
+pub const MIN_RATING: u32 = 100;
+/// Pets with fewer rated battles than this use the provisional K-factor.
+pub const PROVISIONAL_BATTLES: u32 = 10;
+pub const PROVISIONAL_K_FACTOR: u32 = 40;
+pub const STANDARD_K_FACTOR: u32 = 20;
+/// Shortest self-exclusion a player can choose.
+pub const MIN_SELF_EXCLUSION_SECS: i64 = 24 * 60 * 60;
 
 #[program]
 pub mod battle_core {
@@ -15,6 +28,15 @@ pub mod battle_core {
         platform.next_pet_id = 0;
         platform.next_battle_id = 0;
         platform.payout_vault = params.payout_vault;
+        platform.randomness_oracle = params.randomness_oracle;
+        platform.timeouts = TimeoutConfig::default();
+        platform.matchups = MatchupTable::default();
+        platform.stat_rules = StatRules::default();
+        platform.paused = false;
+        platform.pauser = None;
+        platform.pending_authority = None;
+        platform.attester = None;
+        platform.royalty = RoyaltyConfig::default();
         emit!(PlatformInitialized {
             authority: platform.authority,
             payout_vault: params.payout_vault,
@@ -22,13 +44,141 @@ pub mod battle_core {
         Ok(())
     }
 
+    pub fn set_randomness_oracle(
+        ctx: Context<UpdatePlatformConfig>,
+        randomness_oracle: Option<Pubkey>,
+    ) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        platform.randomness_oracle = randomness_oracle;
+        emit!(RandomnessOracleUpdated {
+            authority: ctx.accounts.authority.key(),
+            randomness_oracle,
+        });
+        Ok(())
+    }
+
+    pub fn set_timeouts(ctx: Context<UpdatePlatformConfig>, timeouts: TimeoutConfig) -> Result<()> {
+        timeouts.validate()?;
+        let platform = &mut ctx.accounts.platform;
+        platform.timeouts = timeouts;
+        emit!(TimeoutsUpdated {
+            authority: ctx.accounts.authority.key(),
+            join_timeout_secs: timeouts.join_timeout_secs,
+            turn_timeout_secs: timeouts.turn_timeout_secs,
+            oracle_timeout_secs: timeouts.oracle_timeout_secs,
+        });
+        Ok(())
+    }
+
+    pub fn set_matchup_table(
+        ctx: Context<UpdatePlatformConfig>,
+        matchups: MatchupTable,
+    ) -> Result<()> {
+        matchups.validate()?;
+        let platform = &mut ctx.accounts.platform;
+        platform.matchups = matchups;
+        emit!(MatchupTableUpdated {
+            authority: ctx.accounts.authority.key(),
+            multipliers_bps: matchups.multipliers_bps,
+        });
+        Ok(())
+    }
+
+    pub fn propose_authority_transfer(
+        ctx: Context<UpdatePlatformConfig>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        require!(
+            new_authority != platform.authority && new_authority != Pubkey::default(),
+            BattleError::InvalidPendingAuthority
+        );
+        platform.pending_authority = Some(new_authority);
+        emit!(AuthorityTransferProposed {
+            authority: platform.authority,
+            pending_authority: new_authority,
+        });
+        Ok(())
+    }
+
+    pub fn cancel_authority_transfer(ctx: Context<UpdatePlatformConfig>) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        let pending_authority = platform
+            .pending_authority
+            .take()
+            .ok_or(BattleError::NoPendingAuthority)?;
+        emit!(AuthorityTransferCancelled {
+            authority: platform.authority,
+            pending_authority,
+        });
+        Ok(())
+    }
+
+    pub fn accept_authority_transfer(ctx: Context<AcceptPlatformAuthority>) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        let previous_authority = platform.authority;
+        platform.authority = ctx.accounts.new_authority.key();
+        platform.pending_authority = None;
+        emit!(AuthorityTransferAccepted {
+            previous_authority,
+            new_authority: platform.authority,
+        });
+        Ok(())
+    }
+
+    pub fn set_pauser(ctx: Context<UpdatePlatformConfig>, pauser: Option<Pubkey>) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        platform.pauser = pauser;
+        emit!(PlatformPauserUpdated {
+            authority: ctx.accounts.authority.key(),
+            pauser,
+        });
+        Ok(())
+    }
+
+    pub fn pause_platform(ctx: Context<SetPlatformPause>, reason_code: u16) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        require!(!platform.paused, BattleError::PlatformPaused);
+        platform.paused = true;
+        emit!(PlatformPaused {
+            pauser: ctx.accounts.pauser.key(),
+            reason_code,
+        });
+        Ok(())
+    }
+
+    pub fn unpause_platform(ctx: Context<SetPlatformPause>) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        require!(platform.paused, BattleError::PlatformNotPaused);
+        platform.paused = false;
+        emit!(PlatformUnpaused {
+            pauser: ctx.accounts.pauser.key(),
+        });
+        Ok(())
+    }
+
+    pub fn set_stat_rules(ctx: Context<UpdatePlatformConfig>, stat_rules: StatRules) -> Result<()> {
+        stat_rules.validate()?;
+        let platform = &mut ctx.accounts.platform;
+        platform.stat_rules = stat_rules;
+        emit!(StatRulesUpdated {
+            authority: ctx.accounts.authority.key(),
+            min: stat_rules.min,
+            max: stat_rules.max,
+            tier_budgets: stat_rules.tier_budgets,
+        });
+        Ok(())
+    }
+
     pub fn register_pet(ctx: Context<RegisterPet>, params: RegisterPetParams) -> Result<()> {
         require!(
             params.metadata_uri.len() <= MAX_METADATA_URI_LEN,
             BattleError::MetadataUriTooLong
         );
         params.stats.validate()?;
+        ctx.accounts.platform.stat_rules.check(&params.stats, params.tier)?;
         let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.platform.check_attestation(&ctx.accounts.owner_attestation, now)?;
         let platform = &mut ctx.accounts.platform;
         let pet = &mut ctx.accounts.pet;
         pet.owner = ctx.accounts.owner.key();
@@ -36,9 +186,15 @@ pub mod battle_core {
         pet.pet_id = platform.next_pet_id;
         pet.bump = ctx.bumps.pet;
         pet.stats = params.stats;
+        pet.tier = params.tier;
         pet.metadata_uri = params.metadata_uri;
         pet.created_at = now;
         pet.last_battle_id = None;
+        pet.wins = 0;
+        pet.losses = 0;
+        pet.draws = 0;
+        pet.rating = DEFAULT_RATING;
+        pet.listing_price = None;
         emit!(PetRegistered {
             owner: pet.owner,
             pet: pet.key(),
@@ -51,7 +207,234 @@ pub mod battle_core {
         Ok(())
     }
 
+    pub fn set_royalty_config(
+        ctx: Context<UpdatePlatformConfig>,
+        royalty: RoyaltyConfig,
+    ) -> Result<()> {
+        royalty.validate()?;
+        let platform = &mut ctx.accounts.platform;
+        platform.royalty = royalty;
+        emit!(RoyaltyConfigUpdated {
+            authority: ctx.accounts.authority.key(),
+            royalty_bps: royalty.royalty_bps,
+            royalty_recipient: royalty.royalty_recipient,
+        });
+        Ok(())
+    }
+
+    /// Gives the pet to `new_owner`, cancelling any open listing.
+    pub fn transfer_pet(ctx: Context<UpdatePet>, new_owner: Pubkey) -> Result<()> {
+        let pet = &mut ctx.accounts.pet;
+        pet.ensure_tradeable()?;
+        require_keys_neq!(new_owner, pet.owner, BattleError::InvalidPetTransfer);
+        let previous_owner = pet.owner;
+        pet.owner = new_owner;
+        pet.listing_price = None;
+        emit!(PetTransferred {
+            pet: pet.key(),
+            pet_id: pet.pet_id,
+            previous_owner,
+            new_owner,
+            price: None,
+        });
+        Ok(())
+    }
+
+    /// Lists the pet for sale at `price` lamports. Relisting replaces the price.
+    pub fn list_pet(ctx: Context<UpdatePet>, price: u64) -> Result<()> {
+        require!(price > 0, BattleError::InvalidListingPrice);
+        let pet = &mut ctx.accounts.pet;
+        pet.ensure_tradeable()?;
+        pet.listing_price = Some(price);
+        emit!(PetListingUpdated {
+            pet: pet.key(),
+            pet_id: pet.pet_id,
+            owner: pet.owner,
+            price: pet.listing_price,
+        });
+        Ok(())
+    }
+
+    pub fn delist_pet(ctx: Context<UpdatePet>) -> Result<()> {
+        let pet = &mut ctx.accounts.pet;
+        require!(pet.listing_price.is_some(), BattleError::PetNotListed);
+        pet.listing_price = None;
+        emit!(PetListingUpdated {
+            pet: pet.key(),
+            pet_id: pet.pet_id,
+            owner: pet.owner,
+            price: None,
+        });
+        Ok(())
+    }
+
+    /// Buys a listed pet: the buyer pays the seller directly, less the platform royalty.
+    /// `expected_price` guards against the listing being repriced before execution.
+    pub fn buy_pet(ctx: Context<BuyPet>, expected_price: u64) -> Result<()> {
+        let pet = &ctx.accounts.pet;
+        let price = pet.listing_price.ok_or(BattleError::PetNotListed)?;
+        require!(price == expected_price, BattleError::ListingPriceMismatch);
+        pet.ensure_tradeable()?;
+        require_keys_neq!(ctx.accounts.buyer.key(), pet.owner, BattleError::InvalidPetTransfer);
+
+        let royalty_config = ctx.accounts.platform.royalty;
+        let royalty = royalty_config.royalty_for(price);
+        if royalty > 0 {
+            let royalty_recipient = ctx
+                .accounts
+                .royalty_recipient
+                .as_ref()
+                .ok_or(BattleError::RoyaltyRecipientMismatch)?;
+            require!(
+                royalty_config.royalty_recipient == Some(royalty_recipient.key()),
+                BattleError::RoyaltyRecipientMismatch
+            );
+            let cpi_accounts = system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: royalty_recipient.to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+            system_program::transfer(cpi_ctx, royalty)?;
+        }
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.buyer.to_account_info(),
+            to: ctx.accounts.seller.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, price - royalty)?;
+
+        let pet = &mut ctx.accounts.pet;
+        let seller = pet.owner;
+        pet.owner = ctx.accounts.buyer.key();
+        pet.listing_price = None;
+        emit!(PetTransferred {
+            pet: pet.key(),
+            pet_id: pet.pet_id,
+            previous_owner: seller,
+            new_owner: pet.owner,
+            price: Some(price),
+        });
+        emit!(PetSold {
+            pet: pet.key(),
+            seller,
+            buyer: pet.owner,
+            price,
+            royalty,
+        });
+        Ok(())
+    }
+
+    /// Sets the key allowed to issue player attestations. While set, registering pets and
+    /// creating or joining battles require a current attestation from it.
+    pub fn set_attester(
+        ctx: Context<UpdatePlatformConfig>,
+        attester: Option<Pubkey>,
+    ) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        platform.attester = attester;
+        emit!(AttesterUpdated {
+            authority: ctx.accounts.authority.key(),
+            attester,
+        });
+        Ok(())
+    }
+
+    pub fn issue_attestation(
+        ctx: Context<IssueAttestation>,
+        params: AttestationParams,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.platform = ctx.accounts.platform.key();
+        attestation.player = ctx.accounts.player.key();
+        attestation.bump = ctx.bumps.attestation;
+        attestation.issue(ctx.accounts.attester.key(), &params, now)
+    }
+
+    /// Re-issues an existing attestation, e.g. after it expired or was revoked.
+    pub fn renew_attestation(
+        ctx: Context<RenewAttestation>,
+        params: AttestationParams,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts
+            .attestation
+            .issue(ctx.accounts.attester.key(), &params, now)
+    }
+
+    pub fn revoke_attestation(ctx: Context<RevokeAttestation>) -> Result<()> {
+        let attestation = &mut ctx.accounts.attestation;
+        require!(!attestation.revoked, BattleError::AttestationRevoked);
+        attestation.revoked = true;
+        emit!(AttestationRevoked {
+            player: attestation.player,
+            revoked_by: ctx.accounts.signer.key(),
+            revoked_at: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn initialize_player_limits(ctx: Context<InitializePlayerLimits>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let limits = &mut ctx.accounts.player_limits;
+        limits.player = ctx.accounts.player.key();
+        limits.platform = ctx.accounts.platform.key();
+        limits.bump = ctx.bumps.player_limits;
+        limits.excluded_until = 0;
+        limits.created_at = now;
+        limits.updated_at = now;
+        emit!(PlayerLimitsInitialized {
+            player: limits.player,
+            player_limits: limits.key(),
+        });
+        Ok(())
+    }
+
+    /// Excludes the player for `duration_secs`, or permanently when `None`.
+    /// An exclusion can only ever be extended, never shortened or lifted early.
+    pub fn self_exclude(
+        ctx: Context<UpdatePlayerLimits>,
+        duration_secs: Option<i64>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let requested_until = match duration_secs {
+            Some(duration) => {
+                require!(
+                    duration >= MIN_SELF_EXCLUSION_SECS,
+                    BattleError::InvalidExclusionPeriod
+                );
+                now.checked_add(duration).ok_or(BattleError::Overflow)?
+            }
+            None => PlayerLimits::PERMANENT,
+        };
+        let limits = &mut ctx.accounts.player_limits;
+        let previous_until = limits.excluded_until;
+        limits.excluded_until = previous_until.max(requested_until);
+        limits.updated_at = now;
+        emit!(SelfExclusionUpdated {
+            player: limits.player,
+            previous_until,
+            excluded_until: limits.excluded_until,
+            permanent: limits.excluded_until == PlayerLimits::PERMANENT,
+            updated_at: now,
+        });
+        Ok(())
+    }
+
     pub fn create_battle(ctx: Context<CreateBattle>, params: CreateBattleParams) -> Result<()> {
+        require!(!ctx.accounts.platform.paused, BattleError::PlatformPaused);
+        let now = Clock::get()?.unix_timestamp;
+        PlayerLimits::ensure_eligible(&ctx.accounts.host_limits, now)?;
+        ctx.accounts.platform.check_attestation(&ctx.accounts.host_attestation, now)?;
+        require!(ctx.accounts.host_deny_entry.data_is_empty(), BattleError::WalletDenied);
+        require!(
+            params.best_of % 2 == 1 && params.best_of <= MAX_BEST_OF,
+            BattleError::InvalidRoundCount
+        );
+        if let Some(lock_intent) = params.lock_intent.as_ref() {
+            ctx.accounts.platform.validate_lock_intent(lock_intent)?;
+        }
         let platform = &mut ctx.accounts.platform;
         let host_pet = &mut ctx.accounts.host_pet;
         require_keys_eq!(
@@ -77,9 +460,17 @@ pub mod battle_core {
         battle.host_submission = None;
         battle.challenger_submission = None;
         battle.winner = None;
+        battle.best_of = params.best_of;
+        battle.host_round_wins = 0;
+        battle.challenger_round_wins = 0;
+        battle.host_health = 0;
+        battle.challenger_health = 0;
+        battle.round_results = Vec::new();
         battle.created_at = Clock::get()?.unix_timestamp;
+        battle.turn_started_at = battle.created_at;
         battle.resolved_at = None;
-        battle.vault_lock = params.lock_intent;
+        battle.vault_lock = params.lock_intent.clone();
+        battle.commit_slot = 0;
         host_pet.last_battle_id = Some(battle_id);
         host_pet.mark_committed();
         emit!(BattleCreated {
@@ -92,11 +483,18 @@ pub mod battle_core {
             .next_battle_id
             .checked_add(1)
             .ok_or(BattleError::Overflow)?;
-        // TODO(battle_core -> payout_vault integration): Lock wager via CPI into [`payout_vault`](../payout_vault/src/lib.rs) once vault flows are stabilised.
+        if let Some(lock_intent) = params.lock_intent {
+            ctx.accounts.lock_vault_wager(&lock_intent)?;
+        }
         Ok(())
     }
 
     pub fn join_battle(ctx: Context<JoinBattle>) -> Result<()> {
+        require!(!ctx.accounts.platform.paused, BattleError::PlatformPaused);
+        let now = Clock::get()?.unix_timestamp;
+        PlayerLimits::ensure_eligible(&ctx.accounts.challenger_limits, now)?;
+        ctx.accounts.platform.check_attestation(&ctx.accounts.challenger_attestation, now)?;
+        require!(ctx.accounts.challenger_deny_entry.data_is_empty(), BattleError::WalletDenied);
         let battle = &mut ctx.accounts.battle;
         require!(
             battle.status == BattleStatus::Waiting,
@@ -116,10 +514,13 @@ pub mod battle_core {
             challenger_pet.is_available_for_battle(),
             BattleError::PetUnavailable
         );
+        // Tiers carry different stat budgets, so battles are only matched within a tier.
+        require!(challenger_pet.tier == ctx.accounts.host_pet.tier, BattleError::TierMismatch);
         battle.challenger = Some(ctx.accounts.challenger.key());
         battle.challenger_pet = Some(challenger_pet.key());
         battle.status = BattleStatus::Active;
         battle.turn_index = 0;
+        battle.turn_started_at = Clock::get()?.unix_timestamp;
         battle.host_submission = None;
         battle.challenger_submission = None;
         challenger_pet.last_battle_id = Some(battle.battle_id);
@@ -130,10 +531,13 @@ pub mod battle_core {
             challenger: ctx.accounts.challenger.key(),
             challenger_pet: challenger_pet.key(),
         });
+        if let Some(lock) = ctx.accounts.battle.vault_lock.clone() {
+            ctx.accounts.assign_vault_challenger(&lock)?;
+        }
         Ok(())
     }
 
-    pub fn submit_turn(ctx: Context<SubmitTurn>, params: SubmitTurnParams) -> Result<()> {
+    pub fn commit_turn(ctx: Context<SubmitTurn>, params: CommitTurnParams) -> Result<()> {
         let battle = &mut ctx.accounts.battle;
         require!(
             battle.status == BattleStatus::Active,
@@ -146,7 +550,8 @@ pub mod battle_core {
         let now = ctx.accounts.clock.unix_timestamp;
         let player = ctx.accounts.player.key();
         let submission = TurnSubmission {
-            move_type: params.move_type,
+            commitment: params.commitment,
+            move_type: None,
             submitted_by: player,
             submitted_at: now,
         };
@@ -165,11 +570,37 @@ pub mod battle_core {
         } else {
             return Err(BattleError::Unauthorized.into());
         }
+        battle.commit_slot = ctx.accounts.clock.slot;
+        if battle.host_submission.is_some() && battle.challenger_submission.is_some() {
+            // Both moves are locked in; the reveal phase gets a fresh turn deadline.
+            battle.turn_started_at = now;
+        }
         emit!(TurnSubmitted {
             battle: battle.key(),
             battle_id: battle.battle_id,
             submitter: player,
             round: battle.turn_index,
+        });
+        Ok(())
+    }
+
+    pub fn reveal_turn(ctx: Context<SubmitTurn>, params: RevealTurnParams) -> Result<()> {
+        let battle = &mut ctx.accounts.battle;
+        require!(
+            battle.status == BattleStatus::Active,
+            BattleError::BattleNotActive
+        );
+        let player = ctx.accounts.player.key();
+        battle.reveal(player, params.move_type, &params.salt)?;
+        if battle.moves_revealed() {
+            // Resolution now only waits on the oracle, which gets its own deadline.
+            battle.turn_started_at = ctx.accounts.clock.unix_timestamp;
+        }
+        emit!(TurnRevealed {
+            battle: battle.key(),
+            battle_id: battle.battle_id,
+            submitter: player,
+            round: battle.turn_index,
             move_type: params.move_type,
         });
         Ok(())
@@ -181,12 +612,7 @@ pub mod battle_core {
             battle.status == BattleStatus::Active,
             BattleError::BattleNotActive
         );
-        let host_submission = battle
-            .host_submission
-            .ok_or(BattleError::TurnsIncomplete)?;
-        let challenger_submission = battle
-            .challenger_submission
-            .ok_or(BattleError::TurnsIncomplete)?;
+        let (host_move, challenger_move) = battle.revealed_moves()?;
         let challenger_key = battle
             .challenger
             .ok_or(BattleError::BattleMissingChallenger)?;
@@ -199,71 +625,186 @@ pub mod battle_core {
             ctx.accounts.challenger_pet.owner == challenger_key,
             BattleError::PetOwnershipMismatch
         );
-        let host_score =
-            calculate_power_score(&ctx.accounts.host_pet.stats, host_submission.move_type);
-        let challenger_score = calculate_power_score(
-            &ctx.accounts.challenger_pet.stats,
-            challenger_submission.move_type,
-        );
+        let randomness = match ctx.accounts.platform.randomness_oracle {
+            Some(oracle) => {
+                let account = ctx
+                    .accounts
+                    .randomness
+                    .as_ref()
+                    .ok_or(BattleError::RandomnessMissing)?;
+                Some(OracleRandomness::load_seed(
+                    account,
+                    &oracle,
+                    &battle.key(),
+                    battle.turn_index,
+                    battle.commit_slot,
+                )?)
+            }
+            None => None,
+        };
+        let matchups = &ctx.accounts.platform.matchups;
+        let mut host_score =
+            matchups.damage(&ctx.accounts.host_pet.stats, host_move, challenger_move);
+        let mut challenger_score =
+            matchups.damage(&ctx.accounts.challenger_pet.stats, challenger_move, host_move);
+        if let Some(seed) = randomness.as_ref() {
+            let (host_roll, challenger_roll) = randomness_rolls(seed);
+            host_score = apply_randomness(host_score, host_roll);
+            challenger_score = apply_randomness(challenger_score, challenger_roll);
+        }
         let now = ctx.accounts.clock.unix_timestamp;
-        battle.status = BattleStatus::Completed;
+        if battle.round_results.is_empty() {
+            battle.host_health = ctx.accounts.host_pet.stats.health;
+            battle.challenger_health = ctx.accounts.challenger_pet.stats.health;
+        }
+        let round = battle.turn_index;
+        let result = battle.record_round(host_score, challenger_score);
+        emit!(RoundResolved {
+            battle: battle.key(),
+            battle_id: battle.battle_id,
+            round,
+            winner: result.winner,
+            host_score,
+            challenger_score,
+            host_health: result.host_health,
+            challenger_health: result.challenger_health,
+            randomness,
+        });
         battle.turn_index = battle.turn_index.saturating_add(1);
-        battle.resolved_at = Some(now);
         battle.host_submission = None;
         battle.challenger_submission = None;
-        battle.winner = match host_score.cmp(&challenger_score) {
-            std::cmp::Ordering::Greater => Some(battle.host),
-            std::cmp::Ordering::Less => Some(challenger_key),
-            std::cmp::Ordering::Equal => None,
-        };
-        ctx.accounts.host_pet.clear_battle_lock();
-        ctx.accounts.challenger_pet.clear_battle_lock();
+        if !battle.is_decided() {
+            battle.turn_started_at = now;
+            return Ok(());
+        }
+        battle.status = BattleStatus::Completed;
+        battle.resolved_at = Some(now);
+        battle.winner = battle.overall_winner();
+        let host_pet = &mut ctx.accounts.host_pet;
+        let challenger_pet = &mut ctx.accounts.challenger_pet;
+        let (host_rating_delta, challenger_rating_delta) =
+            record_battle_outcome(battle, host_pet, challenger_pet);
+        host_pet.clear_battle_lock();
+        challenger_pet.clear_battle_lock();
         emit!(BattleResolved {
             battle: battle.key(),
             battle_id: battle.battle_id,
             winner: battle.winner,
             host_score,
             challenger_score,
+            host_round_wins: battle.host_round_wins,
+            challenger_round_wins: battle.challenger_round_wins,
+            randomness,
+            host_rating_delta,
+            challenger_rating_delta,
+            host_rating: ctx.accounts.host_pet.rating,
+            challenger_rating: ctx.accounts.challenger_pet.rating,
         });
-        // TODO(battle_core randomness): Replace deterministic scoring with on-chain VRF integration when available.
-        // TODO(battle_core -> payout_vault integration): Trigger settlement CPI against [`payout_vault`](../payout_vault/src/lib.rs) when vault logic is live.
+        if let Some(lock) = ctx.accounts.battle.vault_lock.clone() {
+            ctx.accounts.settle_vault_wager(&lock)?;
+        }
         Ok(())
     }
 
     pub fn force_settle(ctx: Context<ForceSettle>) -> Result<()> {
         let battle = &mut ctx.accounts.battle;
-        if battle.status != BattleStatus::Completed {
+        let was_open = battle.status.is_open();
+        if was_open {
             battle.status = BattleStatus::Completed;
             battle.resolved_at = Some(Clock::get()?.unix_timestamp);
             battle.host_submission = None;
             battle.challenger_submission = None;
             battle.winner = None;
         }
-        let host_pet = &mut ctx.accounts.host_pet;
-        require_keys_eq!(host_pet.key(), battle.host_pet, BattleError::PetMismatch);
-        host_pet.clear_battle_lock();
-
-        match (battle.challenger_pet, ctx.accounts.challenger_pet.as_mut()) {
-            (Some(expected_key), Some(challenger_pet)) => {
-                require_keys_eq!(challenger_pet.key(), expected_key, BattleError::PetMismatch);
-                let challenger_owner = battle
-                    .challenger
-                    .ok_or(BattleError::BattleMissingChallenger)?;
-                require_keys_eq!(
-                    challenger_pet.owner,
-                    challenger_owner,
-                    BattleError::PetOwnershipMismatch
-                );
-                challenger_pet.clear_battle_lock();
-            }
-            (Some(_), None) => return Err(BattleError::PetMismatch.into()),
-            (None, Some(_)) => return Err(BattleError::PetMismatch.into()),
-            (None, None) => {}
-        }
+        release_battle_pets(
+            battle,
+            &mut ctx.accounts.host_pet,
+            ctx.accounts.challenger_pet.as_mut(),
+        )?;
         emit!(BattleForceSettled {
             battle: battle.key(),
             authority: ctx.accounts.authority.key(),
         });
+        if let Some(lock) = ctx.accounts.battle.vault_lock.clone().filter(|_| was_open) {
+            ctx.accounts.void_vault_wager(&lock)?;
+        }
+        Ok(())
+    }
+
+    pub fn cancel_battle(ctx: Context<CancelBattle>) -> Result<()> {
+        let battle = &mut ctx.accounts.battle;
+        require!(
+            battle.status == BattleStatus::Waiting,
+            BattleError::BattleNotWaiting
+        );
+        battle.status = BattleStatus::Cancelled;
+        battle.resolved_at = Some(Clock::get()?.unix_timestamp);
+        release_battle_pets(battle, &mut ctx.accounts.host_pet, None)?;
+        emit!(BattleCancelled {
+            battle: battle.key(),
+            battle_id: battle.battle_id,
+            host: battle.host,
+        });
+        if let Some(lock) = ctx.accounts.battle.vault_lock.clone() {
+            ctx.accounts.void_vault_wager(&lock)?;
+        }
+        Ok(())
+    }
+
+    pub fn claim_timeout(ctx: Context<ClaimTimeout>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let timeouts = ctx.accounts.platform.timeouts;
+        let battle = &mut ctx.accounts.battle;
+        let deadline = battle.timeout_deadline(&timeouts).ok_or(BattleError::TimeoutDisabled)?;
+        require!(now >= deadline, BattleError::TimeoutNotReached);
+        // Without an oracle a fully revealed round can always be resolved, so it never times out.
+        require!(
+            !battle.moves_revealed() || ctx.accounts.platform.randomness_oracle.is_some(),
+            BattleError::BattleReadyToResolve
+        );
+        let cancelled = battle.status == BattleStatus::Waiting;
+        if cancelled {
+            battle.status = BattleStatus::Cancelled;
+            battle.winner = None;
+        } else {
+            battle.winner = battle.timeout_winner()?;
+            battle.status = BattleStatus::Completed;
+            battle.turn_index = battle.turn_index.saturating_add(1);
+        }
+        battle.resolved_at = Some(now);
+        battle.host_submission = None;
+        battle.challenger_submission = None;
+        release_battle_pets(
+            battle,
+            &mut ctx.accounts.host_pet,
+            ctx.accounts.challenger_pet.as_mut(),
+        )?;
+        // A forfeit is rated like any other result; timeouts without a winner stay unrated.
+        let (host_rating_delta, challenger_rating_delta) =
+            match (battle.winner, ctx.accounts.challenger_pet.as_mut()) {
+                (Some(_), Some(challenger_pet)) => {
+                    record_battle_outcome(battle, &mut ctx.accounts.host_pet, challenger_pet)
+                }
+                _ => (0, 0),
+            };
+        emit!(BattleTimedOut {
+            battle: battle.key(),
+            battle_id: battle.battle_id,
+            claimant: ctx.accounts.claimant.key(),
+            winner: battle.winner,
+            cancelled,
+            host_rating_delta,
+            challenger_rating_delta,
+        });
+        if let Some(lock) = ctx.accounts.battle.vault_lock.clone() {
+            ctx.accounts.settle_vault_wager(&lock, cancelled)?;
+        }
+        // A battle nobody joined is closed like a host cancellation, returning its rent.
+        if cancelled {
+            let host = ctx.accounts.host.as_ref().ok_or(BattleError::HostAccountMissing)?;
+            require_keys_eq!(host.key(), ctx.accounts.battle.host, BattleError::Unauthorized);
+            ctx.accounts.battle.close(host.to_account_info())?;
+        }
         Ok(())
     }
 }
@@ -271,22 +812,40 @@ pub mod battle_core {
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct InitializePlatformParams {
     pub payout_vault: Option<Pubkey>,
+    pub randomness_oracle: Option<Pubkey>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct RegisterPetParams {
     pub stats: PetStats,
+    pub tier: PetTier,
     pub metadata_uri: String,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AttestationParams {
+    /// ISO 3166-1 alpha-2 code of the jurisdiction the player was verified in.
+    pub jurisdiction: [u8; 2],
+    pub expires_at: i64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct CreateBattleParams {
     pub lock_intent: Option<PayoutLockContext>,
+    /// Odd number of rounds, up to [`MAX_BEST_OF`]; the first to a majority wins.
+    pub best_of: u8,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
-pub struct SubmitTurnParams {
+pub struct CommitTurnParams {
+    /// Hash produced by [`turn_commitment`] for the player's move and secret salt.
+    pub commitment: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RevealTurnParams {
     pub move_type: PetMove,
+    pub salt: [u8; 32],
 }
 
 #[derive(Accounts)]
@@ -304,6 +863,43 @@ pub struct InitializePlatform<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct UpdatePlatformConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PlatformState::SEED],
+        bump = platform.bump,
+        has_one = authority @ BattleError::Unauthorized
+    )]
+    pub platform: Account<'info, PlatformState>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptPlatformAuthority<'info> {
+    pub new_authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PlatformState::SEED],
+        bump = platform.bump,
+        constraint = platform.pending_authority == Some(new_authority.key())
+            @ BattleError::InvalidPendingAuthority
+    )]
+    pub platform: Account<'info, PlatformState>,
+}
+
+#[derive(Accounts)]
+pub struct SetPlatformPause<'info> {
+    pub pauser: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PlatformState::SEED],
+        bump = platform.bump,
+        constraint = platform.can_pause(&pauser.key()) @ BattleError::Unauthorized
+    )]
+    pub platform: Account<'info, PlatformState>,
+}
+
 #[derive(Accounts)]
 pub struct RegisterPet<'info> {
     #[account(mut)]
@@ -327,11 +923,155 @@ pub struct RegisterPet<'info> {
     )]
     pub pet: Account<'info, PetState>,
     pub system_program: Program<'info, System>,
+    /// Owner's attestation; required once the platform has an attester.
+    #[account(
+        seeds = [PlayerAttestation::SEED, platform.key().as_ref(), owner.key().as_ref()],
+        bump = owner_attestation.bump
+    )]
+    pub owner_attestation: Option<Account<'info, PlayerAttestation>>,
 }
 
 #[derive(Accounts)]
-pub struct CreateBattle<'info> {
-    #[account(mut)]
+pub struct UpdatePet<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [PlatformState::SEED],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, PlatformState>,
+    #[account(
+        mut,
+        seeds = [PetState::SEED, platform.key().as_ref(), &pet.pet_id.to_le_bytes()],
+        bump = pet.bump,
+        has_one = owner @ BattleError::PetOwnershipMismatch
+    )]
+    pub pet: Account<'info, PetState>,
+}
+
+#[derive(Accounts)]
+pub struct BuyPet<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        seeds = [PlatformState::SEED],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, PlatformState>,
+    #[account(
+        mut,
+        seeds = [PetState::SEED, platform.key().as_ref(), &pet.pet_id.to_le_bytes()],
+        bump = pet.bump
+    )]
+    pub pet: Account<'info, PetState>,
+    /// CHECK: Current owner of the pet; receives the sale proceeds.
+    #[account(mut, address = pet.owner @ BattleError::PetOwnershipMismatch)]
+    pub seller: UncheckedAccount<'info>,
+    /// CHECK: Must match the platform royalty recipient; required when a royalty applies.
+    #[account(mut)]
+    pub royalty_recipient: Option<UncheckedAccount<'info>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct IssueAttestation<'info> {
+    #[account(mut)]
+    pub attester: Signer<'info>,
+    #[account(
+        seeds = [PlatformState::SEED],
+        bump = platform.bump,
+        constraint = platform.attester == Some(attester.key()) @ BattleError::Unauthorized
+    )]
+    pub platform: Account<'info, PlatformState>,
+    /// CHECK: Only used as the attested wallet and PDA seed.
+    pub player: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = attester,
+        seeds = [PlayerAttestation::SEED, platform.key().as_ref(), player.key().as_ref()],
+        bump,
+        space = PlayerAttestation::space()
+    )]
+    pub attestation: Account<'info, PlayerAttestation>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenewAttestation<'info> {
+    pub attester: Signer<'info>,
+    #[account(
+        seeds = [PlatformState::SEED],
+        bump = platform.bump,
+        constraint = platform.attester == Some(attester.key()) @ BattleError::Unauthorized
+    )]
+    pub platform: Account<'info, PlatformState>,
+    #[account(
+        mut,
+        seeds = [
+            PlayerAttestation::SEED,
+            platform.key().as_ref(),
+            attestation.player.as_ref()
+        ],
+        bump = attestation.bump
+    )]
+    pub attestation: Account<'info, PlayerAttestation>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAttestation<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        seeds = [PlatformState::SEED],
+        bump = platform.bump,
+        constraint = platform.can_revoke_attestation(&signer.key()) @ BattleError::Unauthorized
+    )]
+    pub platform: Account<'info, PlatformState>,
+    #[account(
+        mut,
+        seeds = [
+            PlayerAttestation::SEED,
+            platform.key().as_ref(),
+            attestation.player.as_ref()
+        ],
+        bump = attestation.bump
+    )]
+    pub attestation: Account<'info, PlayerAttestation>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePlayerLimits<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+    #[account(
+        seeds = [PlatformState::SEED],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, PlatformState>,
+    #[account(
+        init,
+        payer = player,
+        seeds = [PlayerLimits::SEED, platform.key().as_ref(), player.key().as_ref()],
+        bump,
+        space = PlayerLimits::space()
+    )]
+    pub player_limits: Account<'info, PlayerLimits>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePlayerLimits<'info> {
+    pub player: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PlayerLimits::SEED, player_limits.platform.as_ref(), player.key().as_ref()],
+        bump = player_limits.bump,
+        has_one = player @ BattleError::Unauthorized
+    )]
+    pub player_limits: Account<'info, PlayerLimits>,
+}
+
+#[derive(Accounts)]
+pub struct CreateBattle<'info> {
+    #[account(mut)]
     pub host: Signer<'info>,
     #[account(
         mut,
@@ -349,6 +1089,26 @@ pub struct CreateBattle<'info> {
         bump = host_pet.bump
     )]
     pub host_pet: Account<'info, PetState>,
+    /// CHECK: Host's `PlayerLimits` PDA; it may be uninitialised if no limits were ever set.
+    #[account(seeds = [PlayerLimits::SEED, platform.key().as_ref(), host.key().as_ref()], bump)]
+    pub host_limits: UncheckedAccount<'info>,
+    /// Host's attestation; required once the platform has an attester.
+    #[account(
+        seeds = [PlayerAttestation::SEED, platform.key().as_ref(), host.key().as_ref()],
+        bump = host_attestation.bump
+    )]
+    pub host_attestation: Option<Account<'info, PlayerAttestation>>,
+    /// CHECK: Host's payout_vault `DenyListEntry` PDA; it only exists if the host is denied.
+    #[account(
+        seeds = [
+            payout_vault::DenyListEntry::SEED,
+            platform.payout_vault.unwrap_or_default().as_ref(),
+            host.key().as_ref()
+        ],
+        bump,
+        seeds::program = payout_vault::ID
+    )]
+    pub host_deny_entry: UncheckedAccount<'info>,
     #[account(
         init,
         payer = host,
@@ -362,6 +1122,56 @@ pub struct CreateBattle<'info> {
     )]
     pub battle: Account<'info, BattleState>,
     pub system_program: Program<'info, System>,
+    /// Vault accounts are only required when `lock_intent` is provided.
+    pub vault_program: Option<Program<'info, PayoutVault>>,
+    /// CHECK: Validated against the platform's configured vault; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub vault_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Initialised by payout_vault during the lock CPI.
+    #[account(mut)]
+    pub wager_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Escrow PDA for escrow-mode locks; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub escrow: Option<UncheckedAccount<'info>>,
+    /// CHECK: Host's payout_vault `PlayerWagerLimits` PDA; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub host_wager_limits: Option<UncheckedAccount<'info>>,
+    /// Vault authority; payout_vault requires its signature on house-mode locks.
+    pub vault_authority: Option<Signer<'info>>,
+    /// CHECK: Approved payout_vault `LockHouseWager` proposal, used instead of the vault
+    /// authority's signature once the vault has multisig enabled; validated by payout_vault.
+    #[account(mut)]
+    pub house_lock_proposal: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> CreateBattle<'info> {
+    fn lock_vault_wager(&self, lock: &PayoutLockContext) -> Result<()> {
+        let vault = VaultCpi::new(
+            &self.platform,
+            lock,
+            &self.vault_program,
+            &self.vault_state,
+            &self.wager_state,
+            &self.escrow,
+        )?
+        .with_player_limits(&self.host_wager_limits, &None)
+        .with_attestations(&self.host_attestation, &None)
+        .with_deny_entries(&Some(self.host_deny_entry.clone()), &None)
+        .with_self_exclusions(&Some(self.host_limits.clone()), &None);
+        vault.lock_wager(
+            self.host.to_account_info(),
+            &self.battle,
+            self.system_program.to_account_info(),
+            self.vault_authority.as_ref().map(|signer| signer.to_account_info()),
+            self.house_lock_proposal.as_ref().map(|proposal| proposal.to_account_info()),
+            LockWagerParams {
+                amount: lock.locked_amount,
+                primary_recipient: self.host.key(),
+                secondary_recipient: None,
+                mode: lock.mode.into(),
+            },
+        )
+    }
 }
 
 #[derive(Accounts)]
@@ -384,6 +1194,9 @@ pub struct JoinBattle<'info> {
         bump = battle.bump
     )]
     pub battle: Account<'info, BattleState>,
+    /// Host's pet, read to match the challenger against the same tier.
+    #[account(address = battle.host_pet @ BattleError::PetMismatch)]
+    pub host_pet: Account<'info, PetState>,
     #[account(
         mut,
         seeds = [
@@ -394,7 +1207,67 @@ pub struct JoinBattle<'info> {
         bump = challenger_pet.bump
     )]
     pub challenger_pet: Account<'info, PetState>,
+    /// CHECK: Challenger's `PlayerLimits` PDA; it may be uninitialised if no limits were set.
+    #[account(
+        seeds = [PlayerLimits::SEED, platform.key().as_ref(), challenger.key().as_ref()],
+        bump
+    )]
+    pub challenger_limits: UncheckedAccount<'info>,
+    /// Challenger's attestation; required once the platform has an attester.
+    #[account(
+        seeds = [PlayerAttestation::SEED, platform.key().as_ref(), challenger.key().as_ref()],
+        bump = challenger_attestation.bump
+    )]
+    pub challenger_attestation: Option<Account<'info, PlayerAttestation>>,
+    /// CHECK: Challenger's payout_vault `DenyListEntry` PDA; it only exists if the challenger
+    /// is denied.
+    #[account(
+        seeds = [
+            payout_vault::DenyListEntry::SEED,
+            platform.payout_vault.unwrap_or_default().as_ref(),
+            challenger.key().as_ref()
+        ],
+        bump,
+        seeds::program = payout_vault::ID
+    )]
+    pub challenger_deny_entry: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
+    /// Vault accounts are only required when the battle carries a vault lock.
+    pub vault_program: Option<Program<'info, PayoutVault>>,
+    /// CHECK: Validated against the battle's vault lock; seeds are enforced by payout_vault.
+    pub vault_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub wager_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Escrow PDA for escrow-mode locks; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub escrow: Option<UncheckedAccount<'info>>,
+    /// CHECK: Challenger's payout_vault `PlayerWagerLimits` PDA; seeds are enforced by
+    /// payout_vault.
+    #[account(mut)]
+    pub challenger_wager_limits: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> JoinBattle<'info> {
+    fn assign_vault_challenger(&self, lock: &PayoutLockContext) -> Result<()> {
+        let vault = VaultCpi::new(
+            &self.platform,
+            lock,
+            &self.vault_program,
+            &self.vault_state,
+            &self.wager_state,
+            &self.escrow,
+        )?
+        .with_player_limits(&None, &self.challenger_wager_limits)
+        .with_attestations(&None, &self.challenger_attestation)
+        .with_deny_entries(&None, &Some(self.challenger_deny_entry.clone()))
+        .with_self_exclusions(&None, &Some(self.challenger_limits.clone()));
+        vault.assign_secondary_recipient(
+            &self.battle,
+            self.challenger.to_account_info(),
+            self.system_program.to_account_info(),
+        )
+    }
 }
 
 #[derive(Accounts)]
@@ -459,6 +1332,65 @@ pub struct ResolveBattle<'info> {
     )]
     pub challenger_pet: Account<'info, PetState>,
     pub clock: Sysvar<'info, Clock>,
+    /// CHECK: Owner and layout are validated against the platform's randomness oracle.
+    pub randomness: Option<UncheckedAccount<'info>>,
+    /// Vault accounts are only required when the battle carries a vault lock.
+    pub vault_program: Option<Program<'info, PayoutVault>>,
+    /// CHECK: Validated against the battle's vault lock; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub vault_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub wager_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Escrow PDA for escrow-mode locks; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub escrow: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match `battle.host`; receives the host share and the wager rent.
+    #[account(mut)]
+    pub host: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match `battle.challenger`; receives the challenger share.
+    #[account(mut)]
+    pub challenger: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+    /// CHECK: payout_vault checks it against the vault's fee config when a fee applies.
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+    /// CHECK: Host's payout_vault `PlayerWagerLimits` PDA; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub host_wager_limits: Option<UncheckedAccount<'info>>,
+    /// CHECK: Challenger's payout_vault `PlayerWagerLimits` PDA; seeds are enforced by
+    /// payout_vault.
+    #[account(mut)]
+    pub challenger_wager_limits: Option<UncheckedAccount<'info>>,
+    /// CHECK: Host's payout_vault `DenyListEntry` PDA; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub host_deny_entry: Option<UncheckedAccount<'info>>,
+    /// CHECK: Challenger's payout_vault `DenyListEntry` PDA; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub challenger_deny_entry: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> ResolveBattle<'info> {
+    fn settle_vault_wager(&self, lock: &PayoutLockContext) -> Result<()> {
+        let vault = VaultCpi::new(
+            &self.platform,
+            lock,
+            &self.vault_program,
+            &self.vault_state,
+            &self.wager_state,
+            &self.escrow,
+        )?
+        .with_fee_recipient(&self.fee_recipient)
+        .with_player_limits(&self.host_wager_limits, &self.challenger_wager_limits)
+        .with_deny_entries(&self.host_deny_entry, &self.challenger_deny_entry);
+        vault.finish(
+            &self.battle,
+            lock,
+            self.host.as_ref(),
+            self.challenger.as_ref(),
+            self.system_program.as_ref(),
+        )
+    }
 }
 
 #[derive(Accounts)]
@@ -495,56 +1427,851 @@ pub struct ForceSettle<'info> {
     /// Optional challenger pet account; only required if the challenger joined.
     #[account(mut)]
     pub challenger_pet: Option<Account<'info, PetState>>,
+    /// Vault accounts are only required to refund escrow-mode stakes.
+    pub vault_program: Option<Program<'info, PayoutVault>>,
+    /// CHECK: Validated against the battle's vault lock; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub vault_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub wager_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Escrow PDA for escrow-mode locks; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub escrow: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match `battle.host`; receives the host stake and the wager rent.
+    #[account(mut)]
+    pub host: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match `battle.challenger`; receives the challenger stake.
+    #[account(mut)]
+    pub challenger: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
 }
 
-#[account]
-pub struct PlatformState {
-    pub authority: Pubkey,
-    pub bump: u8,
-    pub next_pet_id: u64,
-    pub next_battle_id: u64,
-    pub payout_vault: Option<Pubkey>,
+impl<'info> ForceSettle<'info> {
+    fn void_vault_wager(&self, lock: &PayoutLockContext) -> Result<()> {
+        let vault = VaultCpi::new(
+            &self.platform,
+            lock,
+            &self.vault_program,
+            &self.vault_state,
+            &self.wager_state,
+            &self.escrow,
+        )?;
+        vault.void(
+            &self.battle,
+            self.host.as_ref(),
+            self.challenger.as_ref(),
+            self.system_program.as_ref(),
+            VoidReason::ForceSettled,
+        )
+    }
 }
 
-impl PlatformState {
-    pub const SEED: &'static [u8] = b"platform";
+#[derive(Accounts)]
+pub struct CancelBattle<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+    #[account(
+        seeds = [PlatformState::SEED],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, PlatformState>,
+    #[account(
+        mut,
+        seeds = [
+            BattleState::SEED,
+            platform.key().as_ref(),
+            &battle.battle_id.to_le_bytes()
+        ],
+        bump = battle.bump,
+        has_one = host @ BattleError::Unauthorized,
+        close = host
+    )]
+    pub battle: Account<'info, BattleState>,
+    #[account(
+        mut,
+        seeds = [
+            PetState::SEED,
+            platform.key().as_ref(),
+            &host_pet.pet_id.to_le_bytes()
+        ],
+        bump = host_pet.bump
+    )]
+    pub host_pet: Account<'info, PetState>,
+    pub system_program: Program<'info, System>,
+    /// Vault accounts are only required when the battle carries a vault lock.
+    pub vault_program: Option<Program<'info, PayoutVault>>,
+    /// CHECK: Validated against the battle's vault lock; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub vault_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub wager_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Escrow PDA for escrow-mode locks; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub escrow: Option<UncheckedAccount<'info>>,
+}
 
-    pub fn space() -> usize {
-        8 + 32 + 1 + 8 + 8 + 1 + 32
+impl<'info> CancelBattle<'info> {
+    fn void_vault_wager(&self, lock: &PayoutLockContext) -> Result<()> {
+        let vault = VaultCpi::new(
+            &self.platform,
+            lock,
+            &self.vault_program,
+            &self.vault_state,
+            &self.wager_state,
+            &self.escrow,
+        )?;
+        vault.void(
+            &self.battle,
+            Some(&self.host),
+            None::<&UncheckedAccount<'info>>,
+            Some(&self.system_program),
+            VoidReason::BattleCancelled,
+        )
     }
 }
 
-#[account]
-pub struct PetState {
-    pub owner: Pubkey,
-    pub platform: Pubkey,
-    pub pet_id: u64,
-    pub bump: u8,
-    pub stats: PetStats,
-    pub metadata_uri: String,
-    pub created_at: i64,
-    pub last_battle_id: Option<u64>,
-    pub committed: bool,
+#[derive(Accounts)]
+pub struct ClaimTimeout<'info> {
+    pub claimant: Signer<'info>,
+    #[account(
+        seeds = [PlatformState::SEED],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, PlatformState>,
+    #[account(
+        mut,
+        seeds = [
+            BattleState::SEED,
+            platform.key().as_ref(),
+            &battle.battle_id.to_le_bytes()
+        ],
+        bump = battle.bump
+    )]
+    pub battle: Account<'info, BattleState>,
+    #[account(
+        mut,
+        seeds = [
+            PetState::SEED,
+            platform.key().as_ref(),
+            &host_pet.pet_id.to_le_bytes()
+        ],
+        bump = host_pet.bump,
+        constraint = host_pet.owner == battle.host @ BattleError::PetOwnershipMismatch
+    )]
+    pub host_pet: Account<'info, PetState>,
+    /// Optional challenger pet account; only required if the challenger joined.
+    #[account(mut)]
+    pub challenger_pet: Option<Account<'info, PetState>>,
+    /// Vault accounts are only required when the battle carries a vault lock.
+    pub vault_program: Option<Program<'info, PayoutVault>>,
+    /// CHECK: Validated against the battle's vault lock; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub vault_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub wager_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Escrow PDA for escrow-mode locks; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub escrow: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match `battle.host`; receives the host share and the wager rent, and
+    /// the battle rent when an unjoined battle is cancelled.
+    #[account(mut)]
+    pub host: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match `battle.challenger`; receives the challenger share.
+    #[account(mut)]
+    pub challenger: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+    /// CHECK: payout_vault checks it against the vault's fee config when a fee applies.
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+    /// CHECK: Host's payout_vault `PlayerWagerLimits` PDA; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub host_wager_limits: Option<UncheckedAccount<'info>>,
+    /// CHECK: Challenger's payout_vault `PlayerWagerLimits` PDA; seeds are enforced by
+    /// payout_vault.
+    #[account(mut)]
+    pub challenger_wager_limits: Option<UncheckedAccount<'info>>,
+    /// CHECK: Host's payout_vault `DenyListEntry` PDA; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub host_deny_entry: Option<UncheckedAccount<'info>>,
+    /// CHECK: Challenger's payout_vault `DenyListEntry` PDA; seeds are enforced by payout_vault.
+    #[account(mut)]
+    pub challenger_deny_entry: Option<UncheckedAccount<'info>>,
 }
 
-impl PetState {
-    pub const SEED: &'static [u8] = b"pet";
+impl<'info> ClaimTimeout<'info> {
+    fn settle_vault_wager(&self, lock: &PayoutLockContext, cancelled: bool) -> Result<()> {
+        let vault = VaultCpi::new(
+            &self.platform,
+            lock,
+            &self.vault_program,
+            &self.vault_state,
+            &self.wager_state,
+            &self.escrow,
+        )?
+        .with_fee_recipient(&self.fee_recipient)
+        .with_player_limits(&self.host_wager_limits, &self.challenger_wager_limits)
+        .with_deny_entries(&self.host_deny_entry, &self.challenger_deny_entry);
+        if cancelled {
+            return vault.void(
+                &self.battle,
+                self.host.as_ref(),
+                self.challenger.as_ref(),
+                self.system_program.as_ref(),
+                VoidReason::JoinTimeout,
+            );
+        }
+        vault.finish(
+            &self.battle,
+            lock,
+            self.host.as_ref(),
+            self.challenger.as_ref(),
+            self.system_program.as_ref(),
+        )
+    }
+}
 
-    pub fn space() -> usize {
-        8 + 32 + 32 + 8 + 1 + PetStats::space() + 4 + MAX_METADATA_URI_LEN + 8 + 1 + 8 + 1
+/// payout_vault accounts forwarded by battle instructions; CPIs are signed by the platform PDA.
+struct VaultCpi<'info> {
+    program: AccountInfo<'info>,
+    vault_state: AccountInfo<'info>,
+    wager_state: AccountInfo<'info>,
+    escrow: Option<AccountInfo<'info>>,
+    fee_recipient: Option<AccountInfo<'info>>,
+    host_limits: Option<AccountInfo<'info>>,
+    challenger_limits: Option<AccountInfo<'info>>,
+    host_attestation: Option<AccountInfo<'info>>,
+    challenger_attestation: Option<AccountInfo<'info>>,
+    host_deny_entry: Option<AccountInfo<'info>>,
+    challenger_deny_entry: Option<AccountInfo<'info>>,
+    host_self_exclusion: Option<AccountInfo<'info>>,
+    challenger_self_exclusion: Option<AccountInfo<'info>>,
+    platform: AccountInfo<'info>,
+    platform_bump: u8,
+}
+
+impl<'info> VaultCpi<'info> {
+    fn new(
+        platform: &Account<'info, PlatformState>,
+        lock: &PayoutLockContext,
+        program: &Option<Program<'info, PayoutVault>>,
+        vault_state: &Option<UncheckedAccount<'info>>,
+        wager_state: &Option<UncheckedAccount<'info>>,
+        escrow: &Option<UncheckedAccount<'info>>,
+    ) -> Result<Self> {
+        let (program, vault_state, wager_state) = match (program, vault_state, wager_state) {
+            (Some(program), Some(vault_state), Some(wager_state)) => {
+                (program, vault_state, wager_state)
+            }
+            _ => return Err(BattleError::VaultAccountsMissing.into()),
+        };
+        require_keys_eq!(vault_state.key(), lock.vault_account, BattleError::VaultMismatch);
+        let escrow = match lock.mode {
+            LockMode::House => None,
+            LockMode::Escrow => Some(
+                escrow.as_ref().ok_or(BattleError::VaultAccountsMissing)?.to_account_info(),
+            ),
+        };
+        Ok(Self {
+            program: program.to_account_info(),
+            vault_state: vault_state.to_account_info(),
+            wager_state: wager_state.to_account_info(),
+            escrow,
+            fee_recipient: None,
+            host_limits: None,
+            challenger_limits: None,
+            host_attestation: None,
+            challenger_attestation: None,
+            host_deny_entry: None,
+            challenger_deny_entry: None,
+            host_self_exclusion: None,
+            challenger_self_exclusion: None,
+            platform: platform.to_account_info(),
+            platform_bump: platform.bump,
+        })
     }
 
-    pub fn is_available_for_battle(&self) -> bool {
-        !self.committed
+    /// Forwards the vault's fee recipient for settlements that may be raked.
+    fn with_fee_recipient(mut self, fee_recipient: &Option<UncheckedAccount<'info>>) -> Self {
+        self.fee_recipient = fee_recipient.as_ref().map(|account| account.to_account_info());
+        self
     }
 
-    pub fn mark_committed(&mut self) {
-        self.committed = true;
+    /// Forwards the players' payout_vault wager limits accounts.
+    fn with_player_limits(
+        mut self,
+        host_limits: &Option<UncheckedAccount<'info>>,
+        challenger_limits: &Option<UncheckedAccount<'info>>,
+    ) -> Self {
+        self.host_limits = host_limits.as_ref().map(|account| account.to_account_info());
+        self.challenger_limits =
+            challenger_limits.as_ref().map(|account| account.to_account_info());
+        self
     }
 
-    pub fn clear_battle_lock(&mut self) {
-        self.committed = false;
-        self.last_battle_id = None;
+    /// Forwards the players' attestations for vaults that gate staking on them.
+    fn with_attestations(
+        mut self,
+        host_attestation: &Option<Account<'info, PlayerAttestation>>,
+        challenger_attestation: &Option<Account<'info, PlayerAttestation>>,
+    ) -> Self {
+        self.host_attestation = host_attestation.as_ref().map(|account| account.to_account_info());
+        self.challenger_attestation =
+            challenger_attestation.as_ref().map(|account| account.to_account_info());
+        self
+    }
+
+    /// Forwards the players' payout_vault deny list entry PDAs.
+    fn with_deny_entries(
+        mut self,
+        host_deny_entry: &Option<UncheckedAccount<'info>>,
+        challenger_deny_entry: &Option<UncheckedAccount<'info>>,
+    ) -> Self {
+        self.host_deny_entry = host_deny_entry.as_ref().map(|account| account.to_account_info());
+        self.challenger_deny_entry =
+            challenger_deny_entry.as_ref().map(|account| account.to_account_info());
+        self
+    }
+
+    /// Forwards the players' `PlayerLimits` PDAs so the vault can enforce self-exclusion.
+    fn with_self_exclusions(
+        mut self,
+        host_limits: &Option<UncheckedAccount<'info>>,
+        challenger_limits: &Option<UncheckedAccount<'info>>,
+    ) -> Self {
+        self.host_self_exclusion = host_limits.as_ref().map(|account| account.to_account_info());
+        self.challenger_self_exclusion =
+            challenger_limits.as_ref().map(|account| account.to_account_info());
+        self
+    }
+
+    fn lock_wager(
+        &self,
+        payer: AccountInfo<'info>,
+        battle: &Account<'info, BattleState>,
+        system_program: AccountInfo<'info>,
+        house_authority: Option<AccountInfo<'info>>,
+        house_lock_proposal: Option<AccountInfo<'info>>,
+        params: LockWagerParams,
+    ) -> Result<()> {
+        // payout_vault reads the battle account, so flush pending changes before the CPI.
+        battle.exit(&crate::ID)?;
+        let seeds = &[PlatformState::SEED, &[self.platform_bump]];
+        let signer_seeds = &[&seeds[..]];
+        let cpi_accounts = payout_vault::cpi::accounts::LockWager {
+            authority: self.platform.clone(),
+            payer,
+            vault_state: self.vault_state.clone(),
+            wager_state: self.wager_state.clone(),
+            battle: battle.to_account_info(),
+            escrow: self.escrow.clone(),
+            system_program,
+            primary_limits: self
+                .host_limits
+                .clone()
+                .ok_or(BattleError::VaultAccountsMissing)?,
+            primary_attestation: self.host_attestation.clone(),
+            primary_deny_entry: self
+                .host_deny_entry
+                .clone()
+                .ok_or(BattleError::VaultAccountsMissing)?,
+            primary_self_exclusion: self
+                .host_self_exclusion
+                .clone()
+                .ok_or(BattleError::VaultAccountsMissing)?,
+            house_authority,
+            proposal: house_lock_proposal,
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(self.program.clone(), cpi_accounts, signer_seeds);
+        payout_vault::cpi::lock_wager(cpi_ctx, params)
+    }
+
+    fn assign_secondary_recipient(
+        &self,
+        battle: &Account<'info, BattleState>,
+        staker: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        require!(!self.wager_expired(), BattleError::VaultWagerExpired);
+        battle.exit(&crate::ID)?;
+        let seeds = &[PlatformState::SEED, &[self.platform_bump]];
+        let signer_seeds = &[&seeds[..]];
+        let recipient = staker.key();
+        let cpi_accounts = payout_vault::cpi::accounts::AssignSecondaryRecipient {
+            authority: self.platform.clone(),
+            vault_state: self.vault_state.clone(),
+            wager_state: self.wager_state.clone(),
+            battle: battle.to_account_info(),
+            staker: Some(staker),
+            escrow: self.escrow.clone(),
+            system_program: Some(system_program),
+            secondary_limits: self
+                .challenger_limits
+                .clone()
+                .ok_or(BattleError::VaultAccountsMissing)?,
+            secondary_attestation: self.challenger_attestation.clone(),
+            secondary_deny_entry: self
+                .challenger_deny_entry
+                .clone()
+                .ok_or(BattleError::VaultAccountsMissing)?,
+            secondary_self_exclusion: self
+                .challenger_self_exclusion
+                .clone()
+                .ok_or(BattleError::VaultAccountsMissing)?,
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(self.program.clone(), cpi_accounts, signer_seeds);
+        payout_vault::cpi::assign_secondary_recipient(cpi_ctx, recipient)
+    }
+
+    /// Settles the wager as `(host, challenger)` amounts; the host also receives the wager rent.
+    fn settle<H, C, S>(
+        &self,
+        battle: &Account<'info, BattleState>,
+        host: Option<&H>,
+        challenger: Option<&C>,
+        system_program: Option<&S>,
+        (host_amount, challenger_amount): (u64, u64),
+    ) -> Result<()>
+    where
+        H: ToAccountInfo<'info> + Key,
+        C: ToAccountInfo<'info> + Key,
+        S: ToAccountInfo<'info>,
+    {
+        let (host, challenger) = participant_accounts(battle, host, challenger)?;
+        let system_program = system_program.ok_or(BattleError::VaultAccountsMissing)?;
+        battle.exit(&crate::ID)?;
+        let seeds = &[PlatformState::SEED, &[self.platform_bump]];
+        let signer_seeds = &[&seeds[..]];
+        let cpi_accounts = payout_vault::cpi::accounts::SettlePayout {
+            authority: self.platform.clone(),
+            vault_state: self.vault_state.clone(),
+            wager_state: self.wager_state.clone(),
+            rent_recipient: host.to_account_info(),
+            battle: battle.to_account_info(),
+            primary_recipient: host.to_account_info(),
+            secondary_recipient: challenger,
+            escrow: self.escrow.clone(),
+            system_program: system_program.to_account_info(),
+            fee_recipient: self.fee_recipient.clone(),
+            proposal: None,
+            primary_limits: self.host_limits.clone(),
+            secondary_limits: self.challenger_limits.clone(),
+            primary_deny_entry: self
+                .host_deny_entry
+                .clone()
+                .ok_or(BattleError::VaultAccountsMissing)?,
+            secondary_deny_entry: self.challenger_deny_entry.clone(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(self.program.clone(), cpi_accounts, signer_seeds);
+        payout_vault::cpi::settle_payout(
+            cpi_ctx,
+            SettlePayoutParams {
+                primary_amount: host_amount,
+                secondary_amount: battle.challenger.map(|_| challenger_amount),
+            },
+        )
+    }
+
+    /// The vault closes the wager account when it expires a stale lock, after which there is
+    /// nothing left to settle or void.
+    fn wager_expired(&self) -> bool {
+        self.wager_state.data_is_empty()
+    }
+
+    /// Pays out a finished battle. Treasury funds only ever go to a winner, so a house-mode
+    /// battle that ends without one releases the reservation instead of splitting it.
+    fn finish<H, C, S>(
+        &self,
+        battle: &Account<'info, BattleState>,
+        lock: &PayoutLockContext,
+        host: Option<&H>,
+        challenger: Option<&C>,
+        system_program: Option<&S>,
+    ) -> Result<()>
+    where
+        H: ToAccountInfo<'info> + Key,
+        C: ToAccountInfo<'info> + Key,
+        S: ToAccountInfo<'info>,
+    {
+        if self.wager_expired() {
+            return Ok(());
+        }
+        if lock.mode == LockMode::House && battle.winner.is_none() {
+            return self.void(battle, host, challenger, system_program, VoidReason::Draw);
+        }
+        let split = payout_split(lock.pot()?, battle.winner, battle.host);
+        self.settle(battle, host, challenger, system_program, split)
+    }
+
+    /// Voids the wager, refunding escrowed stakes to the host and challenger.
+    fn void<H, C, S>(
+        &self,
+        battle: &Account<'info, BattleState>,
+        host: Option<&H>,
+        challenger: Option<&C>,
+        system_program: Option<&S>,
+        reason: VoidReason,
+    ) -> Result<()>
+    where
+        H: ToAccountInfo<'info> + Key,
+        C: ToAccountInfo<'info> + Key,
+        S: ToAccountInfo<'info>,
+    {
+        if self.wager_expired() {
+            return Ok(());
+        }
+        let (host, challenger) = participant_accounts(battle, host, challenger)?;
+        let system_program = system_program.ok_or(BattleError::VaultAccountsMissing)?;
+        battle.exit(&crate::ID)?;
+        let seeds = &[PlatformState::SEED, &[self.platform_bump]];
+        let signer_seeds = &[&seeds[..]];
+        let cpi_accounts = payout_vault::cpi::accounts::VoidWager {
+            authority: self.platform.clone(),
+            vault_state: self.vault_state.clone(),
+            wager_state: self.wager_state.clone(),
+            rent_recipient: host.to_account_info(),
+            battle: battle.to_account_info(),
+            primary_staker: Some(host.to_account_info()),
+            secondary_staker: challenger,
+            escrow: self.escrow.clone(),
+            token_vault: None,
+            system_program: Some(system_program.to_account_info()),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(self.program.clone(), cpi_accounts, signer_seeds);
+        payout_vault::cpi::void_wager(cpi_ctx, reason)
+    }
+}
+
+/// Checks the forwarded host and challenger accounts against the battle's participants.
+fn participant_accounts<'info, H, C>(
+    battle: &BattleState,
+    host: Option<&H>,
+    challenger: Option<&C>,
+) -> Result<(AccountInfo<'info>, Option<AccountInfo<'info>>)>
+where
+    H: ToAccountInfo<'info> + Key,
+    C: ToAccountInfo<'info> + Key,
+{
+    let host = host.ok_or(BattleError::VaultAccountsMissing)?;
+    require_keys_eq!(host.key(), battle.host, BattleError::Unauthorized);
+    let challenger = match battle.challenger {
+        Some(expected) => {
+            let challenger = challenger.ok_or(BattleError::VaultAccountsMissing)?;
+            require_keys_eq!(challenger.key(), expected, BattleError::Unauthorized);
+            Some(challenger.to_account_info())
+        }
+        None => None,
+    };
+    Ok((host.to_account_info(), challenger))
+}
+
+#[account]
+pub struct PlatformState {
+    pub authority: Pubkey,
+    pub bump: u8,
+    pub next_pet_id: u64,
+    pub next_battle_id: u64,
+    pub payout_vault: Option<Pubkey>,
+    pub randomness_oracle: Option<Pubkey>,
+    pub timeouts: TimeoutConfig,
+    pub matchups: MatchupTable,
+    pub stat_rules: StatRules,
+    /// Blocks creating and joining battles; in-flight battles can still resolve.
+    pub paused: bool,
+    pub pauser: Option<Pubkey>,
+    /// Proposed authority that must sign `accept_authority_transfer` to take over.
+    pub pending_authority: Option<Pubkey>,
+    /// Key issuing player attestations; `None` leaves play ungated.
+    pub attester: Option<Pubkey>,
+    pub royalty: RoyaltyConfig,
+}
+
+impl PlatformState {
+    pub const SEED: &'static [u8] = b"platform";
+
+    pub fn space() -> usize {
+        8 + 32
+            + 1
+            + 8
+            + 8
+            + 1
+            + 32
+            + 1
+            + 32
+            + TimeoutConfig::space()
+            + MatchupTable::space()
+            + StatRules::space()
+            + 1
+            + 1
+            + 32
+            + 1
+            + 32
+            + 1
+            + 32
+            + RoyaltyConfig::space()
+    }
+
+    /// The authority can always pause; the pauser can pause but holds no other rights.
+    pub fn can_pause(&self, key: &Pubkey) -> bool {
+        *key == self.authority || Some(*key) == self.pauser
+    }
+
+    pub fn can_revoke_attestation(&self, key: &Pubkey) -> bool {
+        *key == self.authority || Some(*key) == self.attester
+    }
+
+    /// Requires a current attestation from the configured attester, if there is one.
+    /// Attestations from a previous attester stop counting once the key is rotated.
+    pub fn check_attestation(
+        &self,
+        attestation: &Option<Account<PlayerAttestation>>,
+        now: i64,
+    ) -> Result<()> {
+        let Some(attester) = self.attester else {
+            return Ok(());
+        };
+        let attestation = attestation.as_ref().ok_or(BattleError::AttestationRequired)?;
+        require_keys_eq!(attestation.attester, attester, BattleError::AttestationRequired);
+        require!(!attestation.revoked, BattleError::AttestationRevoked);
+        require!(now < attestation.expires_at, BattleError::AttestationExpired);
+        Ok(())
+    }
+
+    pub fn validate_lock_intent(&self, lock: &PayoutLockContext) -> Result<()> {
+        let vault_account = self.payout_vault.ok_or(BattleError::VaultNotConfigured)?;
+        require_keys_eq!(lock.vault_program, payout_vault::ID, BattleError::VaultMismatch);
+        require_keys_eq!(lock.vault_account, vault_account, BattleError::VaultMismatch);
+        require!(lock.locked_amount > 0, BattleError::InvalidLockAmount);
+        Ok(())
+    }
+}
+
+#[account]
+pub struct PetState {
+    pub owner: Pubkey,
+    pub platform: Pubkey,
+    pub pet_id: u64,
+    pub bump: u8,
+    pub stats: PetStats,
+    pub metadata_uri: String,
+    pub created_at: i64,
+    pub last_battle_id: Option<u64>,
+    pub committed: bool,
+    pub tier: PetTier,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub rating: u32,
+    /// Asking price in lamports while the pet is listed for sale.
+    pub listing_price: Option<u64>,
+}
+
+impl PetState {
+    pub const SEED: &'static [u8] = b"pet";
+
+    pub fn space() -> usize {
+        8 + 32
+            + 32
+            + 8
+            + 1
+            + PetStats::space()
+            + 4
+            + MAX_METADATA_URI_LEN
+            + 8
+            + 1
+            + 8
+            + 1
+            + 1
+            + 4 * 4
+            + (1 + 8)
+    }
+
+    pub fn is_available_for_battle(&self) -> bool {
+        !self.committed
+    }
+
+    /// Pets committed to a battle cannot be listed, sold or transferred until it ends.
+    pub fn ensure_tradeable(&self) -> Result<()> {
+        require!(self.is_available_for_battle(), BattleError::PetUnavailable);
+        Ok(())
+    }
+
+    pub fn mark_committed(&mut self) {
+        self.committed = true;
+    }
+
+    pub fn clear_battle_lock(&mut self) {
+        self.committed = false;
+        self.last_battle_id = None;
+    }
+
+    /// Clears the lock only if it is held by `battle_id`, so closing an old battle cannot
+    /// free a pet that has since been committed to another one.
+    pub fn release_from(&mut self, battle_id: u64) -> Result<()> {
+        require!(self.last_battle_id == Some(battle_id), BattleError::PetNotInBattle);
+        self.clear_battle_lock();
+        Ok(())
+    }
+
+    pub fn rated_battles(&self) -> u32 {
+        self.wins
+            .saturating_add(self.losses)
+            .saturating_add(self.draws)
+    }
+
+    pub fn record_result(&mut self, outcome: MatchOutcome, rating_delta: i32) {
+        match outcome {
+            MatchOutcome::Win => self.wins = self.wins.saturating_add(1),
+            MatchOutcome::Loss => self.losses = self.losses.saturating_add(1),
+            MatchOutcome::Draw => self.draws = self.draws.saturating_add(1),
+        }
+        self.rating = self
+            .rating
+            .saturating_add_signed(rating_delta)
+            .max(MIN_RATING);
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchOutcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl MatchOutcome {
+    pub fn reversed(self) -> Self {
+        match self {
+            MatchOutcome::Win => MatchOutcome::Loss,
+            MatchOutcome::Loss => MatchOutcome::Win,
+            MatchOutcome::Draw => MatchOutcome::Draw,
+        }
+    }
+
+    /// Actual score in basis points: 10_000 for a win, 5_000 for a draw.
+    fn score_bps(self) -> i64 {
+        match self {
+            MatchOutcome::Win => 10_000,
+            MatchOutcome::Loss => 0,
+            MatchOutcome::Draw => 5_000,
+        }
+    }
+}
+
+pub const MAX_ROYALTY_BPS: u16 = 1_000;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Platform cut of marketplace pet sales.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoyaltyConfig {
+    /// Royalty on the sale price, in basis points.
+    pub royalty_bps: u16,
+    pub royalty_recipient: Option<Pubkey>,
+}
+
+impl RoyaltyConfig {
+    pub const fn space() -> usize {
+        2 + (1 + 32)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.royalty_bps <= MAX_ROYALTY_BPS, BattleError::InvalidRoyaltyConfig);
+        require!(
+            self.royalty_bps == 0 || self.royalty_recipient.is_some(),
+            BattleError::InvalidRoyaltyConfig
+        );
+        Ok(())
+    }
+
+    pub fn royalty_for(&self, price: u64) -> u64 {
+        (price as u128 * self.royalty_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+}
+
+/// Off-chain KYC, age and jurisdiction clearance for a player, issued by the platform attester.
+#[account]
+pub struct PlayerAttestation {
+    pub platform: Pubkey,
+    pub player: Pubkey,
+    pub bump: u8,
+    pub attester: Pubkey,
+    /// ISO 3166-1 alpha-2 code of the jurisdiction the player was verified in.
+    pub jurisdiction: [u8; 2],
+    pub issued_at: i64,
+    pub expires_at: i64,
+    pub revoked: bool,
+}
+
+impl PlayerAttestation {
+    pub const SEED: &'static [u8] = b"attestation";
+
+    pub fn space() -> usize {
+        8 + 32 + 32 + 1 + 32 + 2 + 8 + 8 + 1
+    }
+
+    fn issue(&mut self, attester: Pubkey, params: &AttestationParams, now: i64) -> Result<()> {
+        require!(
+            params.jurisdiction.iter().all(u8::is_ascii_uppercase),
+            BattleError::InvalidAttestation
+        );
+        require!(params.expires_at > now, BattleError::InvalidAttestation);
+        self.attester = attester;
+        self.jurisdiction = params.jurisdiction;
+        self.issued_at = now;
+        self.expires_at = params.expires_at;
+        self.revoked = false;
+        emit!(AttestationIssued {
+            player: self.player,
+            attester,
+            jurisdiction: self.jurisdiction,
+            issued_at: now,
+            expires_at: self.expires_at,
+        });
+        Ok(())
+    }
+}
+
+/// Per-player responsible-gaming settings.
+#[account]
+pub struct PlayerLimits {
+    pub player: Pubkey,
+    pub platform: Pubkey,
+    pub bump: u8,
+    /// Unix timestamp the self-exclusion runs until; `PERMANENT` for a permanent exclusion.
+    pub excluded_until: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl PlayerLimits {
+    pub const SEED: &'static [u8] = b"player-limits";
+    pub const PERMANENT: i64 = i64::MAX;
+
+    pub fn space() -> usize {
+        8 + 32 + 32 + 1 + 8 + 8 + 8
+    }
+
+    pub fn is_excluded(&self, now: i64) -> bool {
+        now < self.excluded_until
+    }
+
+    /// Rejects players with an active exclusion. `account` is the seed-checked PDA; a
+    /// player who never created one has no limits to enforce.
+    pub fn ensure_eligible(account: &UncheckedAccount, now: i64) -> Result<()> {
+        if account.data_is_empty() {
+            return Ok(());
+        }
+        require_keys_eq!(*account.owner, crate::ID, BattleError::Unauthorized);
+        let data = account.try_borrow_data()?;
+        let limits = PlayerLimits::try_deserialize(&mut &data[..])?;
+        require!(!limits.is_excluded(now), BattleError::PlayerSelfExcluded);
+        Ok(())
     }
 }
 
@@ -562,9 +2289,20 @@ pub struct BattleState {
     pub host_submission: Option<TurnSubmission>,
     pub challenger_submission: Option<TurnSubmission>,
     pub winner: Option<Pubkey>,
+    pub best_of: u8,
+    pub host_round_wins: u8,
+    pub challenger_round_wins: u8,
+    /// Remaining health pools, seeded from `PetStats::health` when the first round resolves.
+    pub host_health: u16,
+    pub challenger_health: u16,
+    pub round_results: Vec<RoundResult>,
     pub created_at: i64,
+    /// Start of the current commit or reveal phase; drives the turn timeout.
+    pub turn_started_at: i64,
     pub resolved_at: Option<i64>,
     pub vault_lock: Option<PayoutLockContext>,
+    /// Slot of the latest move commitment; oracle randomness must be fulfilled after it.
+    pub commit_slot: u64,
 }
 
 impl BattleState {
@@ -584,88 +2322,652 @@ impl BattleState {
             + (1 + TurnSubmission::space())
             + (1 + TurnSubmission::space())
             + (1 + 32)
+            + 1
+            + 1
+            + 1
+            + 2
+            + 2
+            + (4 + MAX_BEST_OF as usize * RoundResult::space())
+            + 8
             + 8
             + (1 + 8)
             + (1 + PayoutLockContext::space())
+            + 8
+    }
+
+    pub fn required_round_wins(&self) -> u8 {
+        self.best_of / 2 + 1
+    }
+
+    /// Applies a round: the loser's health pool absorbs the score difference as damage.
+    pub fn record_round(&mut self, host_score: u64, challenger_score: u64) -> RoundResult {
+        let damage = |margin: u64| u16::try_from(margin).unwrap_or(u16::MAX);
+        let winner = match host_score.cmp(&challenger_score) {
+            std::cmp::Ordering::Greater => {
+                self.host_round_wins = self.host_round_wins.saturating_add(1);
+                self.challenger_health = self
+                    .challenger_health
+                    .saturating_sub(damage(host_score - challenger_score));
+                Some(self.host)
+            }
+            std::cmp::Ordering::Less => {
+                self.challenger_round_wins = self.challenger_round_wins.saturating_add(1);
+                self.host_health =
+                    self.host_health.saturating_sub(damage(challenger_score - host_score));
+                self.challenger
+            }
+            std::cmp::Ordering::Equal => None,
+        };
+        let result = RoundResult {
+            host_score,
+            challenger_score,
+            host_health: self.host_health,
+            challenger_health: self.challenger_health,
+            winner,
+        };
+        self.round_results.push(result);
+        result
+    }
+
+    /// A battle ends on a knockout, once a side holds a majority of rounds, or when all
+    /// `best_of` rounds have been played.
+    pub fn is_decided(&self) -> bool {
+        let required = self.required_round_wins();
+        self.host_health == 0
+            || self.challenger_health == 0
+            || self.host_round_wins >= required
+            || self.challenger_round_wins >= required
+            || self.round_results.len() >= self.best_of as usize
+    }
+
+    /// Knockouts win outright, then round wins, then remaining health; otherwise a draw.
+    pub fn overall_winner(&self) -> Option<Pubkey> {
+        let host_ahead = (self.challenger_health == 0, self.host_round_wins, self.host_health);
+        let challenger_ahead =
+            (self.host_health == 0, self.challenger_round_wins, self.challenger_health);
+        match host_ahead.cmp(&challenger_ahead) {
+            std::cmp::Ordering::Greater => Some(self.host),
+            std::cmp::Ordering::Less => self.challenger,
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Records `player`'s move once both commitments are in, provided it and `salt` hash to
+    /// the player's stored commitment.
+    pub fn reveal(&mut self, player: Pubkey, move_type: PetMove, salt: &[u8; 32]) -> Result<()> {
+        require!(
+            self.host_submission.is_some() && self.challenger_submission.is_some(),
+            BattleError::CommitmentsIncomplete
+        );
+        let submission = if player == self.host {
+            self.host_submission.as_mut()
+        } else if Some(player) == self.challenger {
+            self.challenger_submission.as_mut()
+        } else {
+            return Err(BattleError::Unauthorized.into());
+        }
+        .ok_or(BattleError::CommitmentsIncomplete)?;
+        require!(submission.move_type.is_none(), BattleError::TurnAlreadyRevealed);
+        require!(
+            turn_commitment(move_type, salt, &player) == submission.commitment,
+            BattleError::CommitmentMismatch
+        );
+        submission.move_type = Some(move_type);
+        Ok(())
+    }
+
+    /// The host's and challenger's revealed moves for the current round.
+    pub fn revealed_moves(&self) -> Result<(PetMove, PetMove)> {
+        let revealed = |submission: Option<TurnSubmission>| {
+            submission.and_then(|turn| turn.move_type).ok_or(BattleError::TurnsIncomplete)
+        };
+        Ok((revealed(self.host_submission)?, revealed(self.challenger_submission)?))
+    }
+
+    /// Whether both players have revealed the current round's moves.
+    pub fn moves_revealed(&self) -> bool {
+        [self.host_submission, self.challenger_submission]
+            .iter()
+            .all(|submission| submission.is_some_and(|turn| turn.move_type.is_some()))
+    }
+
+    /// Timestamp after which `claim_timeout` may close the battle, if a timeout applies.
+    pub fn timeout_deadline(&self, timeouts: &TimeoutConfig) -> Option<i64> {
+        let (started_at, timeout) = match self.status {
+            BattleStatus::Waiting => (self.created_at, timeouts.join_timeout_secs),
+            BattleStatus::Active if self.moves_revealed() => {
+                (self.turn_started_at, timeouts.oracle_timeout_secs)
+            }
+            BattleStatus::Active => (self.turn_started_at, timeouts.turn_timeout_secs),
+            BattleStatus::Completed | BattleStatus::Cancelled => return None,
+        };
+        if timeout == 0 {
+            return None;
+        }
+        Some(started_at.saturating_add(timeout))
+    }
+
+    /// Winner by forfeit: the only player who acted in the stalled phase, or `None` if neither
+    /// did. A round stalled on oracle randomness after both reveals ends without a winner.
+    pub fn timeout_winner(&self) -> Result<Option<Pubkey>> {
+        let challenger = self.challenger.ok_or(BattleError::BattleMissingChallenger)?;
+        // Once both commitments exist the stalled phase is the reveal, so only reveals count.
+        let submissions = (self.host_submission, self.challenger_submission);
+        let (host_acted, challenger_acted) = match submissions {
+            (Some(host_turn), Some(challenger_turn)) => {
+                (host_turn.move_type.is_some(), challenger_turn.move_type.is_some())
+            }
+            (host_turn, challenger_turn) => (host_turn.is_some(), challenger_turn.is_some()),
+        };
+        match (host_acted, challenger_acted) {
+            (true, false) => Ok(Some(self.host)),
+            (false, true) => Ok(Some(challenger)),
+            (false, false) | (true, true) => Ok(None),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BattleStatus {
+    Waiting,
+    Active,
+    Completed,
+    Cancelled,
+}
+
+impl BattleStatus {
+    pub fn is_open(self) -> bool {
+        matches!(self, Self::Waiting | Self::Active)
+    }
+}
+
+impl Default for BattleStatus {
+    fn default() -> Self {
+        Self::Waiting
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PetMove {
+    Strike,
+    Guard,
+    Blitz,
+}
+
+impl PetMove {
+    pub const ALL: [PetMove; 3] = [PetMove::Strike, PetMove::Guard, PetMove::Blitz];
+
+    /// The move this one has the advantage over: Guard blunts Strike, Blitz outpaces Guard
+    /// and Strike catches Blitz.
+    pub fn counters(self) -> PetMove {
+        match self {
+            PetMove::Strike => PetMove::Blitz,
+            PetMove::Guard => PetMove::Strike,
+            PetMove::Blitz => PetMove::Guard,
+        }
+    }
+}
+
+/// Damage multipliers in basis points, indexed `[attacker move][defender move]`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchupTable {
+    pub multipliers_bps: [[u16; 3]; 3],
+}
+
+impl MatchupTable {
+    pub const NEUTRAL_BPS: u16 = 10_000;
+    pub const DEFAULT_ADVANTAGE_BPS: u16 = 15_000;
+    pub const DEFAULT_DISADVANTAGE_BPS: u16 = 5_000;
+    pub const MAX_MULTIPLIER_BPS: u16 = 50_000;
+
+    pub const fn space() -> usize {
+        2 * 3 * 3
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for multiplier in self.multipliers_bps.iter().flatten() {
+            require!(
+                *multiplier > 0 && *multiplier <= Self::MAX_MULTIPLIER_BPS,
+                BattleError::InvalidMatchupMultiplier
+            );
+        }
+        Ok(())
+    }
+
+    pub fn multiplier_bps(&self, attacker: PetMove, defender: PetMove) -> u16 {
+        self.multipliers_bps[attacker as usize][defender as usize]
+    }
+
+    /// Damage dealt by `attacker_move` into `defender_move`: the attacker's power score
+    /// scaled by the pairing's multiplier.
+    pub fn damage(&self, stats: &PetStats, attacker_move: PetMove, defender_move: PetMove) -> u64 {
+        let multiplier = self.multiplier_bps(attacker_move, defender_move) as u64;
+        calculate_power_score(stats, attacker_move).saturating_mul(multiplier)
+            / Self::NEUTRAL_BPS as u64
+    }
+}
+
+impl Default for MatchupTable {
+    fn default() -> Self {
+        let mut multipliers_bps = [[Self::NEUTRAL_BPS; 3]; 3];
+        for attacker in PetMove::ALL {
+            for defender in PetMove::ALL {
+                multipliers_bps[attacker as usize][defender as usize] =
+                    if attacker.counters() == defender {
+                        Self::DEFAULT_ADVANTAGE_BPS
+                    } else if defender.counters() == attacker {
+                        Self::DEFAULT_DISADVANTAGE_BPS
+                    } else {
+                        Self::NEUTRAL_BPS
+                    };
+            }
+        }
+        Self { multipliers_bps }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TurnSubmission {
+    pub commitment: [u8; 32],
+    /// Populated once the committed move has been revealed.
+    pub move_type: Option<PetMove>,
+    pub submitted_by: Pubkey,
+    pub submitted_at: i64,
+}
+
+impl TurnSubmission {
+    pub const fn space() -> usize {
+        32 + (1 + 1) + 32 + 8
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoundResult {
+    pub host_score: u64,
+    pub challenger_score: u64,
+    /// Health pools after this round's damage was applied.
+    pub host_health: u16,
+    pub challenger_health: u16,
+    pub winner: Option<Pubkey>,
+}
+
+impl RoundResult {
+    pub const fn space() -> usize {
+        8 + 8 + 2 + 2 + (1 + 32)
+    }
+}
+
+/// battle_core locks lamport wagers only; MATKA wagers are house-backed and are locked and
+/// settled by the vault operators through `lock_wager_token`/`settle_payout_token`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PayoutLockContext {
+    pub vault_program: Pubkey,
+    pub vault_account: Pubkey,
+    /// House reservation, or each player's stake in escrow mode.
+    pub locked_amount: u64,
+    pub mode: LockMode,
+}
+
+impl PayoutLockContext {
+    pub const fn space() -> usize {
+        32 + 32 + 8 + 1
+    }
+
+    /// Total lamports released at settlement once both sides are locked.
+    pub fn pot(&self) -> Result<u64> {
+        match self.mode {
+            LockMode::House => Ok(self.locked_amount),
+            LockMode::Escrow => {
+                self.locked_amount.checked_mul(2).ok_or_else(|| BattleError::Overflow.into())
+            }
+        }
+    }
+}
+
+/// Mirrors `payout_vault::WagerMode` so the battle IDL stays self-contained.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockMode {
+    /// House treasury funds are reserved for the payout.
+    House,
+    /// Host and challenger each deposit `locked_amount` into the wager escrow.
+    Escrow,
+}
+
+impl From<LockMode> for WagerMode {
+    fn from(mode: LockMode) -> Self {
+        match mode {
+            LockMode::House => WagerMode::House,
+            LockMode::Escrow => WagerMode::Escrow,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PetStats {
+    pub health: u16,
+    pub attack: u16,
+    pub defense: u16,
+    pub speed: u16,
+}
+
+impl PetStats {
+    pub const fn space() -> usize {
+        2 + 2 + 2 + 2
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.health > 0, BattleError::InvalidStats);
+        require!(self.attack > 0, BattleError::InvalidStats);
+        require!(self.defense > 0, BattleError::InvalidStats);
+        require!(self.speed > 0, BattleError::InvalidStats);
+        Ok(())
+    }
+
+    pub fn values(&self) -> [u16; 4] {
+        [self.health, self.attack, self.defense, self.speed]
+    }
+
+    pub fn total(&self) -> u32 {
+        self.values().iter().map(|stat| *stat as u32).sum()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PetTier {
+    Rookie,
+    Veteran,
+    Elite,
+}
+
+/// Registration limits for pet stats: per-stat bounds plus a total point budget per tier.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatRules {
+    pub min: PetStats,
+    pub max: PetStats,
+    /// Maximum sum of all four stats, indexed by [`PetTier`].
+    pub tier_budgets: [u32; 3],
+}
+
+impl StatRules {
+    pub const fn space() -> usize {
+        PetStats::space() + PetStats::space() + 4 * 3
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.min.validate().map_err(|_| BattleError::InvalidStatRules)?;
+        for (min, max) in self.min.values().iter().zip(self.max.values()) {
+            require!(*min <= max, BattleError::InvalidStatRules);
+        }
+        require!(self.tier_budgets[0] >= self.min.total(), BattleError::InvalidStatRules);
+        require!(
+            self.tier_budgets.windows(2).all(|pair| pair[0] <= pair[1]),
+            BattleError::InvalidStatRules
+        );
+        Ok(())
+    }
+
+    pub fn check(&self, stats: &PetStats, tier: PetTier) -> Result<()> {
+        let bounds = self.min.values().into_iter().zip(self.max.values());
+        for (stat, (min, max)) in stats.values().into_iter().zip(bounds) {
+            require!(stat >= min, BattleError::StatBelowMinimum);
+            require!(stat <= max, BattleError::StatAboveMaximum);
+        }
+        require!(
+            stats.total() <= self.tier_budgets[tier as usize],
+            BattleError::StatBudgetExceeded
+        );
+        Ok(())
+    }
+}
+
+impl Default for StatRules {
+    fn default() -> Self {
+        Self {
+            min: PetStats { health: 1, attack: 1, defense: 1, speed: 1 },
+            max: PetStats { health: 100, attack: 50, defense: 50, speed: 50 },
+            tier_budgets: [120, 160, 200],
+        }
+    }
+}
+
+pub fn calculate_power_score(stats: &PetStats, move_type: PetMove) -> u64 {
+    let base = stats.attack as u64 + stats.defense as u64 + stats.speed as u64;
+    let bonus = match move_type {
+        PetMove::Strike => (stats.attack as u64).saturating_mul(2),
+        PetMove::Guard => (stats.defense as u64).saturating_mul(2),
+        PetMove::Blitz => (stats.speed as u64).saturating_mul(2),
+    };
+    base + bonus + (stats.health as u64)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeoutConfig {
+    /// Seconds a `Waiting` battle may go unjoined; 0 disables the timeout.
+    pub join_timeout_secs: i64,
+    /// Seconds each commit or reveal phase may take; 0 disables the timeout.
+    pub turn_timeout_secs: i64,
+    /// Seconds a fully revealed round may wait for oracle randomness before it can be claimed
+    /// as a draw; 0 disables the timeout.
+    pub oracle_timeout_secs: i64,
+}
+
+impl TimeoutConfig {
+    pub const DEFAULT_JOIN_TIMEOUT_SECS: i64 = 24 * 60 * 60;
+    pub const DEFAULT_TURN_TIMEOUT_SECS: i64 = 15 * 60;
+    pub const DEFAULT_ORACLE_TIMEOUT_SECS: i64 = 60 * 60;
+    /// Longest window any timeout may be set to; payout_vault's minimum wager lock
+    /// duration stays above it.
+    pub const MAX_TIMEOUT_SECS: i64 = 3 * 24 * 60 * 60;
+
+    pub const fn space() -> usize {
+        8 + 8 + 8
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            (0..=Self::MAX_TIMEOUT_SECS).contains(&self.join_timeout_secs),
+            BattleError::InvalidTimeout
+        );
+        require!(
+            (0..=Self::MAX_TIMEOUT_SECS).contains(&self.turn_timeout_secs),
+            BattleError::InvalidTimeout
+        );
+        require!(
+            (0..=Self::MAX_TIMEOUT_SECS).contains(&self.oracle_timeout_secs),
+            BattleError::InvalidTimeout
+        );
+        Ok(())
+    }
+}
+
+const _: () = assert!(TimeoutConfig::MAX_TIMEOUT_SECS < payout_vault::MIN_MAX_LOCK_DURATION_SECS);
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            join_timeout_secs: Self::DEFAULT_JOIN_TIMEOUT_SECS,
+            turn_timeout_secs: Self::DEFAULT_TURN_TIMEOUT_SECS,
+            oracle_timeout_secs: Self::DEFAULT_ORACLE_TIMEOUT_SECS,
+        }
     }
 }
 
+/// Account layout battle_core reads from a randomness oracle: an 8-byte discriminator
+/// followed by these fields. Any oracle program writing this prefix can be plugged in.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
-pub enum BattleStatus {
-    Waiting,
-    Active,
-    Completed,
+pub struct OracleRandomness {
+    pub battle: Pubkey,
+    pub round: u8,
+    pub seed: [u8; 32],
+    pub fulfilled: bool,
+    pub fulfilled_at: i64,
+    pub fulfilled_slot: u64,
 }
 
-impl Default for BattleStatus {
-    fn default() -> Self {
-        Self::Waiting
+impl OracleRandomness {
+    pub const fn space() -> usize {
+        32 + 1 + 32 + 1 + 8 + 8
     }
-}
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
-pub enum PetMove {
-    Strike,
-    Guard,
-    Blitz,
+    /// Returns the seed once the oracle owning `account` has fulfilled it for `battle` and
+    /// `round` in a slot after `commit_slot`, so the seed cannot be known before both moves
+    /// are committed.
+    pub fn load_seed(
+        account: &AccountInfo<'_>,
+        oracle: &Pubkey,
+        battle: &Pubkey,
+        round: u8,
+        commit_slot: u64,
+    ) -> Result<[u8; 32]> {
+        require_keys_eq!(*account.owner, *oracle, BattleError::RandomnessOracleMismatch);
+        let data = account.try_borrow_data()?;
+        require!(data.len() >= 8 + Self::space(), BattleError::RandomnessOracleMismatch);
+        let randomness = Self::deserialize(&mut &data[8..])?;
+        require_keys_eq!(randomness.battle, *battle, BattleError::RandomnessOracleMismatch);
+        require!(randomness.round == round, BattleError::RandomnessOracleMismatch);
+        require!(randomness.fulfilled, BattleError::RandomnessNotFulfilled);
+        require!(randomness.fulfilled_slot > commit_slot, BattleError::RandomnessStale);
+        Ok(randomness.seed)
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
-pub struct TurnSubmission {
-    pub move_type: PetMove,
-    pub submitted_by: Pubkey,
-    pub submitted_at: i64,
+/// Maximum random bonus, as a fraction of the base score, applied during resolution.
+pub const RANDOMNESS_VARIANCE_DIVISOR: u64 = 10;
+
+/// Splits an oracle seed into independent `(host, challenger)` rolls.
+pub fn randomness_rolls(seed: &[u8; 32]) -> (u64, u64) {
+    let mut host = [0u8; 8];
+    let mut challenger = [0u8; 8];
+    host.copy_from_slice(&seed[..8]);
+    challenger.copy_from_slice(&seed[8..16]);
+    (u64::from_le_bytes(host), u64::from_le_bytes(challenger))
 }
 
-impl TurnSubmission {
-    pub const fn space() -> usize {
-        1 + 32 + 8
-    }
+/// Adds a roll-derived bonus of at most `score / RANDOMNESS_VARIANCE_DIVISOR`.
+pub fn apply_randomness(score: u64, roll: u64) -> u64 {
+    score.saturating_add(roll % (score / RANDOMNESS_VARIANCE_DIVISOR + 1))
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
-pub struct PayoutLockContext {
-    pub vault_program: Pubkey,
-    pub vault_account: Pubkey,
-    pub locked_amount: u64,
+/// Commitment hash for a hidden move: `sha256(move || salt || player)`.
+/// Binding the player key stops an opponent from replaying someone else's commitment.
+pub fn turn_commitment(move_type: PetMove, salt: &[u8; 32], player: &Pubkey) -> [u8; 32] {
+    hashv(&[&[move_type as u8], salt, player.as_ref()]).to_bytes()
 }
 
-impl PayoutLockContext {
-    pub const fn space() -> usize {
-        32 + 32 + 8
+/// Clears the battle lock on both pets after checking they belong to, and are still locked
+/// to, `battle`.
+pub fn release_battle_pets(
+    battle: &BattleState,
+    host_pet: &mut Account<PetState>,
+    challenger_pet: Option<&mut Account<PetState>>,
+) -> Result<()> {
+    require_keys_eq!(host_pet.key(), battle.host_pet, BattleError::PetMismatch);
+    host_pet.release_from(battle.battle_id)?;
+
+    match (battle.challenger_pet, challenger_pet) {
+        (Some(expected_key), Some(challenger_pet)) => {
+            require_keys_eq!(challenger_pet.key(), expected_key, BattleError::PetMismatch);
+            let challenger_owner = battle
+                .challenger
+                .ok_or(BattleError::BattleMissingChallenger)?;
+            require_keys_eq!(
+                challenger_pet.owner,
+                challenger_owner,
+                BattleError::PetOwnershipMismatch
+            );
+            challenger_pet.release_from(battle.battle_id)?;
+        }
+        (Some(_), None) => return Err(BattleError::PetMismatch.into()),
+        (None, Some(_)) => return Err(BattleError::PetMismatch.into()),
+        (None, None) => {}
     }
+    Ok(())
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
-pub struct PetStats {
-    pub health: u16,
-    pub attack: u16,
-    pub defense: u16,
-    pub speed: u16,
+/// Records the finished battle's result on both pets and returns their rating deltas.
+pub fn record_battle_outcome(
+    battle: &BattleState,
+    host_pet: &mut PetState,
+    challenger_pet: &mut PetState,
+) -> (i32, i32) {
+    let host_outcome = match battle.winner {
+        Some(winner) if winner == battle.host => MatchOutcome::Win,
+        Some(_) => MatchOutcome::Loss,
+        None => MatchOutcome::Draw,
+    };
+    let (host_rating_delta, challenger_rating_delta) = rating_deltas(
+        (host_pet.rating, host_pet.rated_battles()),
+        (challenger_pet.rating, challenger_pet.rated_battles()),
+        host_outcome,
+    );
+    host_pet.record_result(host_outcome, host_rating_delta);
+    challenger_pet.record_result(host_outcome.reversed(), challenger_rating_delta);
+    (host_rating_delta, challenger_rating_delta)
 }
 
-impl PetStats {
-    pub const fn space() -> usize {
-        2 + 2 + 2 + 2
+/// Expected score of the higher-rated side, in basis points, at 50-point rating gaps.
+const EXPECTED_SCORE_BPS: [u32; 17] = [
+    5000, 5715, 6401, 7034, 7597, 8083, 8490, 8823, 9091, 9302, 9468, 9595, 9693, 9768, 9825,
+    9868, 9901,
+];
+const EXPECTED_SCORE_STEP: u32 = 50;
+
+/// Elo expected score of `rating` against `opponent_rating` in basis points.
+/// Uses a lookup table with linear interpolation so it stays integer-only on-chain.
+pub fn expected_score_bps(rating: u32, opponent_rating: u32) -> u32 {
+    let gap = rating.abs_diff(opponent_rating);
+    let max_index = EXPECTED_SCORE_BPS.len() - 1;
+    let index = ((gap / EXPECTED_SCORE_STEP) as usize).min(max_index);
+    let favourite = if index == max_index {
+        EXPECTED_SCORE_BPS[max_index]
+    } else {
+        let low = EXPECTED_SCORE_BPS[index];
+        let high = EXPECTED_SCORE_BPS[index + 1];
+        low + (high - low) * (gap % EXPECTED_SCORE_STEP) / EXPECTED_SCORE_STEP
+    };
+    if rating >= opponent_rating {
+        favourite
+    } else {
+        10_000 - favourite
     }
+}
 
-    pub fn validate(&self) -> Result<()> {
-        require!(self.health > 0, BattleError::InvalidStats);
-        require!(self.attack > 0, BattleError::InvalidStats);
-        require!(self.defense > 0, BattleError::InvalidStats);
-        require!(self.speed > 0, BattleError::InvalidStats);
-        Ok(())
+pub fn k_factor(rated_battles: u32) -> u32 {
+    if rated_battles < PROVISIONAL_BATTLES {
+        PROVISIONAL_K_FACTOR
+    } else {
+        STANDARD_K_FACTOR
     }
 }
 
-pub fn calculate_power_score(stats: &PetStats, move_type: PetMove) -> u64 {
-    let base = stats.attack as u64 + stats.defense as u64 + stats.speed as u64;
-    let bonus = match move_type {
-        PetMove::Strike => (stats.attack as u64).saturating_mul(2),
-        PetMove::Guard => (stats.defense as u64).saturating_mul(2),
-        PetMove::Blitz => (stats.speed as u64).saturating_mul(2),
+/// Rating changes for both sides of a battle given each side's `(rating, rated_battles)`
+/// and the outcome from the first side's perspective. Each side uses its own K-factor,
+/// so provisional pets move faster than established ones.
+pub fn rating_deltas(first: (u32, u32), second: (u32, u32), outcome: MatchOutcome) -> (i32, i32) {
+    let delta = |(rating, battles): (u32, u32), opponent: u32, outcome: MatchOutcome| {
+        let expected = expected_score_bps(rating, opponent) as i64;
+        let swing = k_factor(battles) as i64 * (outcome.score_bps() - expected);
+        // Round half away from zero so small edges still move the rating.
+        ((swing + swing.signum() * 5_000) / 10_000) as i32
     };
-    base + bonus + (stats.health as u64)
+    (
+        delta(first, second.0, outcome),
+        delta(second, first.0, outcome.reversed()),
+    )
+}
+
+/// Splits a locked wager into `(host, challenger)` shares for the recorded winner.
+/// Escrow draws refund both sides evenly, with any odd lamport returned to the host; house
+/// draws never reach this split because the reservation is voided instead.
+pub fn payout_split(locked_amount: u64, winner: Option<Pubkey>, host: Pubkey) -> (u64, u64) {
+    match winner {
+        Some(winner) if winner == host => (locked_amount, 0),
+        Some(_) => (0, locked_amount),
+        None => {
+            let challenger_share = locked_amount / 2;
+            (locked_amount - challenger_share, challenger_share)
+        }
+    }
 }
 
 #[event]
@@ -703,16 +3005,201 @@ pub struct TurnSubmitted {
     pub battle_id: u64,
     pub submitter: Pubkey,
     pub round: u8,
+}
+
+#[event]
+pub struct TurnRevealed {
+    pub battle: Pubkey,
+    pub battle_id: u64,
+    pub submitter: Pubkey,
+    pub round: u8,
     pub move_type: PetMove,
 }
 
+#[event]
+pub struct RoundResolved {
+    pub battle: Pubkey,
+    pub battle_id: u64,
+    pub round: u8,
+    pub winner: Option<Pubkey>,
+    pub host_score: u64,
+    pub challenger_score: u64,
+    pub host_health: u16,
+    pub challenger_health: u16,
+    pub randomness: Option<[u8; 32]>,
+}
+
 #[event]
 pub struct BattleResolved {
     pub battle: Pubkey,
     pub battle_id: u64,
     pub winner: Option<Pubkey>,
+    /// Scores from the deciding round.
     pub host_score: u64,
     pub challenger_score: u64,
+    pub host_round_wins: u8,
+    pub challenger_round_wins: u8,
+    /// Oracle seed mixed into the scores, if the platform has a randomness oracle configured.
+    pub randomness: Option<[u8; 32]>,
+    pub host_rating_delta: i32,
+    pub challenger_rating_delta: i32,
+    /// Pet ratings after the deltas were applied.
+    pub host_rating: u32,
+    pub challenger_rating: u32,
+}
+
+#[event]
+pub struct RandomnessOracleUpdated {
+    pub authority: Pubkey,
+    pub randomness_oracle: Option<Pubkey>,
+}
+
+#[event]
+pub struct BattleCancelled {
+    pub battle: Pubkey,
+    pub battle_id: u64,
+    pub host: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferProposed {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferCancelled {
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferAccepted {
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct PlatformPauserUpdated {
+    pub authority: Pubkey,
+    pub pauser: Option<Pubkey>,
+}
+
+#[event]
+pub struct PlatformPaused {
+    pub pauser: Pubkey,
+    pub reason_code: u16,
+}
+
+#[event]
+pub struct PlatformUnpaused {
+    pub pauser: Pubkey,
+}
+
+#[event]
+pub struct RoyaltyConfigUpdated {
+    pub authority: Pubkey,
+    pub royalty_bps: u16,
+    pub royalty_recipient: Option<Pubkey>,
+}
+
+#[event]
+pub struct PetTransferred {
+    pub pet: Pubkey,
+    pub pet_id: u64,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+    /// Sale price when the transfer came from a marketplace purchase.
+    pub price: Option<u64>,
+}
+
+#[event]
+pub struct PetListingUpdated {
+    pub pet: Pubkey,
+    pub pet_id: u64,
+    pub owner: Pubkey,
+    /// `None` once the listing is withdrawn.
+    pub price: Option<u64>,
+}
+
+#[event]
+pub struct PetSold {
+    pub pet: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub royalty: u64,
+}
+
+#[event]
+pub struct AttesterUpdated {
+    pub authority: Pubkey,
+    pub attester: Option<Pubkey>,
+}
+
+#[event]
+pub struct AttestationIssued {
+    pub player: Pubkey,
+    pub attester: Pubkey,
+    pub jurisdiction: [u8; 2],
+    pub issued_at: i64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct AttestationRevoked {
+    pub player: Pubkey,
+    pub revoked_by: Pubkey,
+    pub revoked_at: i64,
+}
+
+#[event]
+pub struct PlayerLimitsInitialized {
+    pub player: Pubkey,
+    pub player_limits: Pubkey,
+}
+
+#[event]
+pub struct SelfExclusionUpdated {
+    pub player: Pubkey,
+    pub previous_until: i64,
+    pub excluded_until: i64,
+    pub permanent: bool,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct StatRulesUpdated {
+    pub authority: Pubkey,
+    pub min: PetStats,
+    pub max: PetStats,
+    pub tier_budgets: [u32; 3],
+}
+
+#[event]
+pub struct MatchupTableUpdated {
+    pub authority: Pubkey,
+    pub multipliers_bps: [[u16; 3]; 3],
+}
+
+#[event]
+pub struct TimeoutsUpdated {
+    pub authority: Pubkey,
+    pub join_timeout_secs: i64,
+    pub turn_timeout_secs: i64,
+    pub oracle_timeout_secs: i64,
+}
+
+#[event]
+pub struct BattleTimedOut {
+    pub battle: Pubkey,
+    pub battle_id: u64,
+    pub claimant: Pubkey,
+    pub winner: Option<Pubkey>,
+    /// True when the battle was never joined and has been cancelled.
+    pub cancelled: bool,
+    pub host_rating_delta: i32,
+    pub challenger_rating_delta: i32,
 }
 
 #[event]
@@ -749,4 +3236,502 @@ pub enum BattleError {
     TurnsIncomplete,
     #[msg("Pet account does not match the battle record")]
     PetMismatch,
-}
\ No newline at end of file
+    #[msg("Platform has no payout vault configured")]
+    VaultNotConfigured,
+    #[msg("Vault accounts do not match the platform configuration")]
+    VaultMismatch,
+    #[msg("Vault accounts are required for battles with a wager lock")]
+    VaultAccountsMissing,
+    #[msg("Locked wager amount must be greater than zero")]
+    InvalidLockAmount,
+    #[msg("Both players must commit before moves can be revealed")]
+    CommitmentsIncomplete,
+    #[msg("Turn has already been revealed")]
+    TurnAlreadyRevealed,
+    #[msg("Revealed move does not match the stored commitment")]
+    CommitmentMismatch,
+    #[msg("Randomness account is required when an oracle is configured")]
+    RandomnessMissing,
+    #[msg("Randomness account is not owned by the configured oracle or targets another battle")]
+    RandomnessOracleMismatch,
+    #[msg("Randomness has not been fulfilled yet")]
+    RandomnessNotFulfilled,
+    #[msg("Randomness must be fulfilled in a slot after both moves were committed")]
+    RandomnessStale,
+    #[msg("Timeouts must be between zero and the maximum timeout")]
+    InvalidTimeout,
+    #[msg("No timeout applies to this battle")]
+    TimeoutDisabled,
+    #[msg("Battle deadline has not passed yet")]
+    TimeoutNotReached,
+    #[msg("Both moves are revealed; resolve the battle instead")]
+    BattleReadyToResolve,
+    #[msg("Battles must be best of an odd number of rounds within the platform maximum")]
+    InvalidRoundCount,
+    #[msg("Matchup multipliers must be positive and within the allowed maximum")]
+    InvalidMatchupMultiplier,
+    #[msg("Stat rules must have positive minimums, min <= max and non-decreasing tier budgets")]
+    InvalidStatRules,
+    #[msg("A pet stat is below the platform minimum")]
+    StatBelowMinimum,
+    #[msg("A pet stat exceeds the platform maximum")]
+    StatAboveMaximum,
+    #[msg("Total pet stats exceed the budget for the selected tier")]
+    StatBudgetExceeded,
+    #[msg("Platform is paused")]
+    PlatformPaused,
+    #[msg("Platform is not paused")]
+    PlatformNotPaused,
+    #[msg("No authority transfer is pending")]
+    NoPendingAuthority,
+    #[msg("Signer or proposed key is not a valid pending authority")]
+    InvalidPendingAuthority,
+    #[msg("Self-exclusion period is shorter than the minimum")]
+    InvalidExclusionPeriod,
+    #[msg("Player is self-excluded")]
+    PlayerSelfExcluded,
+    #[msg("A current attestation from the platform attester is required")]
+    AttestationRequired,
+    #[msg("Attestation has been revoked")]
+    AttestationRevoked,
+    #[msg("Attestation has expired")]
+    AttestationExpired,
+    #[msg("Attestation jurisdiction or expiry is invalid")]
+    InvalidAttestation,
+    #[msg("Wallet is on the payout vault deny list")]
+    WalletDenied,
+    #[msg("Pet cannot be transferred to its current owner")]
+    InvalidPetTransfer,
+    #[msg("Listing price must be greater than zero")]
+    InvalidListingPrice,
+    #[msg("Pet is not listed for sale")]
+    PetNotListed,
+    #[msg("Listing price differs from the price the buyer agreed to")]
+    ListingPriceMismatch,
+    #[msg("Royalty exceeds the maximum or no royalty recipient is configured")]
+    InvalidRoyaltyConfig,
+    #[msg("Royalty recipient does not match the platform royalty config")]
+    RoyaltyRecipientMismatch,
+    #[msg("Challenger pet must be in the same tier as the host pet")]
+    TierMismatch,
+    #[msg("The vault wager for this battle has expired")]
+    VaultWagerExpired,
+    #[msg("Pet is not locked to this battle")]
+    PetNotInBattle,
+    #[msg("The host account is required to close a cancelled battle")]
+    HostAccountMissing,
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeouts_are_capped_below_the_vault_lock_floor() {
+        let mut timeouts = TimeoutConfig::default();
+        assert!(timeouts.validate().is_ok());
+        timeouts.join_timeout_secs = TimeoutConfig::MAX_TIMEOUT_SECS;
+        assert!(timeouts.validate().is_ok());
+        timeouts.join_timeout_secs += 1;
+        assert!(timeouts.validate().is_err());
+    }
+
+    fn active_battle(host: Pubkey, challenger: Pubkey) -> BattleState {
+        BattleState {
+            platform: Pubkey::new_unique(),
+            battle_id: 1,
+            bump: 255,
+            host,
+            host_pet: Pubkey::new_unique(),
+            challenger: Some(challenger),
+            challenger_pet: Some(Pubkey::new_unique()),
+            status: BattleStatus::Active,
+            turn_index: 0,
+            host_submission: None,
+            challenger_submission: None,
+            winner: None,
+            best_of: 3,
+            host_round_wins: 0,
+            challenger_round_wins: 0,
+            host_health: 0,
+            challenger_health: 0,
+            round_results: vec![],
+            created_at: 0,
+            turn_started_at: 0,
+            resolved_at: None,
+            vault_lock: None,
+            commit_slot: 0,
+        }
+    }
+
+    fn commitment(player: Pubkey, move_type: PetMove, salt: &[u8; 32]) -> TurnSubmission {
+        TurnSubmission {
+            commitment: turn_commitment(move_type, salt, &player),
+            move_type: None,
+            submitted_by: player,
+            submitted_at: 0,
+        }
+    }
+
+    /// Battle where both players have committed: the host to Strike, the challenger to Guard.
+    fn committed_battle() -> (BattleState, Pubkey, Pubkey) {
+        let (host, challenger) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut battle = active_battle(host, challenger);
+        battle.host_submission = Some(commitment(host, PetMove::Strike, &[1; 32]));
+        battle.challenger_submission = Some(commitment(challenger, PetMove::Guard, &[2; 32]));
+        (battle, host, challenger)
+    }
+
+    #[test]
+    fn reveals_moves_matching_their_commitments() {
+        let (mut battle, host, challenger) = committed_battle();
+        battle.reveal(host, PetMove::Strike, &[1; 32]).unwrap();
+        assert!(!battle.moves_revealed());
+        assert_eq!(
+            battle.reveal(host, PetMove::Strike, &[1; 32]),
+            Err(BattleError::TurnAlreadyRevealed.into())
+        );
+        battle.reveal(challenger, PetMove::Guard, &[2; 32]).unwrap();
+        assert!(battle.moves_revealed());
+        assert_eq!(battle.revealed_moves(), Ok((PetMove::Strike, PetMove::Guard)));
+    }
+
+    #[test]
+    fn rejects_a_reveal_with_the_wrong_move_or_salt() {
+        let (mut battle, host, _) = committed_battle();
+        assert_eq!(
+            battle.reveal(host, PetMove::Strike, &[3; 32]),
+            Err(BattleError::CommitmentMismatch.into())
+        );
+        assert_eq!(
+            battle.reveal(host, PetMove::Blitz, &[1; 32]),
+            Err(BattleError::CommitmentMismatch.into())
+        );
+        assert_eq!(battle.host_submission.unwrap().move_type, None);
+    }
+
+    #[test]
+    fn rejects_a_commitment_copied_from_the_opponent() {
+        let (mut battle, host, challenger) = committed_battle();
+        battle.challenger_submission = battle.host_submission;
+        assert_eq!(
+            battle.reveal(challenger, PetMove::Strike, &[1; 32]),
+            Err(BattleError::CommitmentMismatch.into())
+        );
+        battle.reveal(host, PetMove::Strike, &[1; 32]).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_reveal_before_both_commitments() {
+        let (mut battle, host, _) = committed_battle();
+        battle.challenger_submission = None;
+        assert_eq!(
+            battle.reveal(host, PetMove::Strike, &[1; 32]),
+            Err(BattleError::CommitmentsIncomplete.into())
+        );
+    }
+
+    #[test]
+    fn resolution_requires_both_moves_revealed() {
+        let (mut battle, host, _) = committed_battle();
+        assert_eq!(battle.revealed_moves(), Err(BattleError::TurnsIncomplete.into()));
+        battle.reveal(host, PetMove::Strike, &[1; 32]).unwrap();
+        assert_eq!(battle.revealed_moves(), Err(BattleError::TurnsIncomplete.into()));
+    }
+
+    #[test]
+    fn a_majority_of_rounds_decides_the_battle() {
+        let (host, challenger) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut battle = active_battle(host, challenger);
+        (battle.host_health, battle.challenger_health) = (1_000, 1_000);
+        assert_eq!(battle.required_round_wins(), 2);
+
+        let round = battle.record_round(120, 100);
+        assert_eq!(round.winner, Some(host));
+        assert_eq!((round.host_health, round.challenger_health), (1_000, 980));
+        assert!(!battle.is_decided());
+        assert_eq!(battle.record_round(90, 100).winner, Some(challenger));
+        assert_eq!(battle.host_health, 990);
+        assert!(!battle.is_decided());
+        assert_eq!(battle.record_round(130, 100).winner, Some(host));
+
+        assert!(battle.is_decided());
+        assert_eq!((battle.host_round_wins, battle.challenger_round_wins), (2, 1));
+        assert_eq!(battle.overall_winner(), Some(host));
+        assert_eq!(battle.round_results.len(), 3);
+    }
+
+    #[test]
+    fn a_depleted_health_pool_ends_the_battle_early() {
+        let (host, challenger) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut battle = active_battle(host, challenger);
+        battle.best_of = 5;
+        (battle.host_health, battle.challenger_health) = (500, 50);
+
+        // The challenger leads on rounds, but the host's one win knocks them out.
+        battle.record_round(100, 110);
+        battle.record_round(100, 110);
+        assert!(!battle.is_decided());
+        let round = battle.record_round(u64::MAX, 0);
+        assert_eq!(round.challenger_health, 0);
+        assert!(battle.is_decided());
+        assert_eq!(battle.overall_winner(), Some(host));
+    }
+
+    #[test]
+    fn level_rounds_fall_back_to_remaining_health_then_a_draw() {
+        let (host, challenger) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut battle = active_battle(host, challenger);
+        (battle.host_health, battle.challenger_health) = (1_000, 1_000);
+        battle.record_round(110, 100);
+        battle.record_round(100, 130);
+        assert_eq!(battle.record_round(100, 100).winner, None);
+        assert!(battle.is_decided());
+        assert_eq!(battle.overall_winner(), Some(challenger));
+
+        let mut battle = active_battle(host, challenger);
+        (battle.host_health, battle.challenger_health) = (1_000, 1_000);
+        for _ in 0..3 {
+            battle.record_round(100, 100);
+        }
+        assert!(battle.is_decided());
+        assert_eq!(battle.overall_winner(), None);
+    }
+
+    #[test]
+    fn default_matchups_follow_the_counter_cycle() {
+        use PetMove::{Blitz, Guard, Strike};
+        let table = MatchupTable::default();
+        let advantage = MatchupTable::DEFAULT_ADVANTAGE_BPS;
+        let disadvantage = MatchupTable::DEFAULT_DISADVANTAGE_BPS;
+        let neutral = MatchupTable::NEUTRAL_BPS;
+        for (attacker, defender, expected) in [
+            (Strike, Strike, neutral),
+            (Strike, Guard, disadvantage),
+            (Strike, Blitz, advantage),
+            (Guard, Strike, advantage),
+            (Guard, Guard, neutral),
+            (Guard, Blitz, disadvantage),
+            (Blitz, Strike, disadvantage),
+            (Blitz, Guard, advantage),
+            (Blitz, Blitz, neutral),
+        ] {
+            assert_eq!(
+                table.multiplier_bps(attacker, defender),
+                expected,
+                "{attacker:?} into {defender:?}"
+            );
+        }
+        assert!(table.validate().is_ok());
+    }
+
+    #[test]
+    fn matchup_damage_scales_the_power_score() {
+        let stats = PetStats { health: 10, attack: 20, defense: 10, speed: 10 };
+        assert_eq!(calculate_power_score(&stats, PetMove::Strike), 90);
+        let table = MatchupTable::default();
+        assert_eq!(table.damage(&stats, PetMove::Strike, PetMove::Strike), 90);
+        assert_eq!(table.damage(&stats, PetMove::Strike, PetMove::Blitz), 135);
+        assert_eq!(table.damage(&stats, PetMove::Strike, PetMove::Guard), 45);
+    }
+
+    #[test]
+    fn matchup_multipliers_must_be_positive_and_capped() {
+        let mut table = MatchupTable::default();
+        table.multipliers_bps[1][2] = MatchupTable::MAX_MULTIPLIER_BPS;
+        assert!(table.validate().is_ok());
+        table.multipliers_bps[1][2] = MatchupTable::MAX_MULTIPLIER_BPS + 1;
+        assert_eq!(table.validate(), Err(BattleError::InvalidMatchupMultiplier.into()));
+        table.multipliers_bps[1][2] = 0;
+        assert_eq!(table.validate(), Err(BattleError::InvalidMatchupMultiplier.into()));
+    }
+
+    #[test]
+    fn stat_rules_bound_every_stat() {
+        let rules = StatRules::default();
+        let stats = PetStats { health: 40, attack: 20, defense: 20, speed: 20 };
+        assert!(rules.check(&stats, PetTier::Rookie).is_ok());
+        for index in 0..4 {
+            let mut values = stats.values();
+            values[index] = rules.min.values()[index] - 1;
+            let [health, attack, defense, speed] = values;
+            let below = PetStats { health, attack, defense, speed };
+            assert_eq!(
+                rules.check(&below, PetTier::Elite),
+                Err(BattleError::StatBelowMinimum.into()),
+                "stat {index}"
+            );
+            values[index] = rules.max.values()[index] + 1;
+            let [health, attack, defense, speed] = values;
+            let above = PetStats { health, attack, defense, speed };
+            assert_eq!(
+                rules.check(&above, PetTier::Elite),
+                Err(BattleError::StatAboveMaximum.into()),
+                "stat {index}"
+            );
+        }
+    }
+
+    #[test]
+    fn stat_budgets_grow_with_the_tier() {
+        let rules = StatRules::default();
+        let rookie = PetStats { health: 60, attack: 30, defense: 20, speed: 10 };
+        assert_eq!(rookie.total(), rules.tier_budgets[PetTier::Rookie as usize]);
+        assert!(rules.check(&rookie, PetTier::Rookie).is_ok());
+
+        let veteran = PetStats { health: 70, ..rookie };
+        assert_eq!(
+            rules.check(&veteran, PetTier::Rookie),
+            Err(BattleError::StatBudgetExceeded.into())
+        );
+        assert!(rules.check(&veteran, PetTier::Veteran).is_ok());
+
+        let maxed = rules.max;
+        assert!(maxed.total() > rules.tier_budgets[PetTier::Elite as usize]);
+        assert_eq!(
+            rules.check(&maxed, PetTier::Elite),
+            Err(BattleError::StatBudgetExceeded.into())
+        );
+    }
+
+    #[test]
+    fn stat_rules_must_be_consistent() {
+        assert!(StatRules::default().validate().is_ok());
+        let mut rules = StatRules::default();
+        rules.max.speed = rules.min.speed - 1;
+        assert_eq!(rules.validate(), Err(BattleError::InvalidStatRules.into()));
+        let rules = StatRules { tier_budgets: [160, 120, 200], ..StatRules::default() };
+        assert_eq!(rules.validate(), Err(BattleError::InvalidStatRules.into()));
+        let mut rules = StatRules::default();
+        rules.tier_budgets[0] = rules.min.total() - 1;
+        assert_eq!(rules.validate(), Err(BattleError::InvalidStatRules.into()));
+    }
+
+    #[test]
+    fn royalty_is_taken_in_basis_points_rounding_down() {
+        let royalty =
+            RoyaltyConfig { royalty_bps: 250, royalty_recipient: Some(Pubkey::new_unique()) };
+        assert!(royalty.validate().is_ok());
+        assert_eq!(royalty.royalty_for(1_000_000), 25_000);
+        // 2.5% of 999 lamports is 24.975; the fraction stays with the seller.
+        assert_eq!(royalty.royalty_for(999), 24);
+        assert_eq!(royalty.royalty_for(39), 0);
+        // The product is taken in u128, so the largest price does not overflow.
+        assert_eq!(royalty.royalty_for(u64::MAX), 461_168_601_842_738_790);
+
+        let capped = RoyaltyConfig { royalty_bps: MAX_ROYALTY_BPS, ..royalty };
+        assert!(capped.validate().is_ok());
+        assert_eq!(capped.royalty_for(1_000_000), 100_000);
+        let excessive = RoyaltyConfig { royalty_bps: MAX_ROYALTY_BPS + 1, ..royalty };
+        assert_eq!(excessive.validate(), Err(BattleError::InvalidRoyaltyConfig.into()));
+    }
+
+    #[test]
+    fn zero_royalty_needs_no_recipient() {
+        let royalty = RoyaltyConfig::default();
+        assert!(royalty.validate().is_ok());
+        assert_eq!(royalty.royalty_for(1_000_000), 0);
+        let unpaid = RoyaltyConfig { royalty_bps: 100, royalty_recipient: None };
+        assert_eq!(unpaid.validate(), Err(BattleError::InvalidRoyaltyConfig.into()));
+    }
+
+    fn pet() -> PetState {
+        PetState {
+            owner: Pubkey::new_unique(),
+            platform: Pubkey::new_unique(),
+            pet_id: 1,
+            bump: 255,
+            stats: PetStats { health: 40, attack: 20, defense: 20, speed: 20 },
+            metadata_uri: String::new(),
+            created_at: 0,
+            last_battle_id: None,
+            committed: false,
+            tier: PetTier::Rookie,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            rating: 1_200,
+            listing_price: None,
+        }
+    }
+
+    #[test]
+    fn committed_pets_cannot_be_traded() {
+        let mut pet = pet();
+        assert!(pet.ensure_tradeable().is_ok());
+        pet.mark_committed();
+        assert_eq!(pet.ensure_tradeable(), Err(BattleError::PetUnavailable.into()));
+        pet.clear_battle_lock();
+        assert!(pet.ensure_tradeable().is_ok());
+    }
+
+    #[test]
+    fn only_the_locking_battle_releases_a_pet() {
+        let mut pet = pet();
+        assert_eq!(pet.release_from(7), Err(BattleError::PetNotInBattle.into()));
+        pet.mark_committed();
+        pet.last_battle_id = Some(8);
+        assert_eq!(pet.release_from(7), Err(BattleError::PetNotInBattle.into()));
+        assert!(!pet.is_available_for_battle());
+        pet.release_from(8).unwrap();
+        assert!(pet.is_available_for_battle());
+        assert_eq!(pet.last_battle_id, None);
+    }
+
+    #[test]
+    fn expected_score_is_even_for_equal_ratings() {
+        assert_eq!(expected_score_bps(1200, 1200), 5000);
+        assert_eq!(expected_score_bps(MIN_RATING, MIN_RATING), 5000);
+    }
+
+    #[test]
+    fn expected_score_interpolates_and_is_symmetric() {
+        assert_eq!(expected_score_bps(1250, 1200), 5715);
+        assert_eq!(expected_score_bps(1225, 1200), 5357);
+        for gap in [1, 49, 50, 137, 400, 799] {
+            let total = expected_score_bps(1200 + gap, 1200) + expected_score_bps(1200, 1200 + gap);
+            assert_eq!(total, 10_000, "gap {gap}");
+        }
+    }
+
+    #[test]
+    fn expected_score_saturates_for_large_gaps() {
+        assert_eq!(expected_score_bps(2000, 1200), 9901);
+        assert_eq!(expected_score_bps(3000, 1200), 9901);
+        assert_eq!(expected_score_bps(1200, 3000), 99);
+    }
+
+    #[test]
+    fn k_factor_drops_after_provisional_battles() {
+        assert_eq!(k_factor(0), PROVISIONAL_K_FACTOR);
+        assert_eq!(k_factor(PROVISIONAL_BATTLES - 1), PROVISIONAL_K_FACTOR);
+        assert_eq!(k_factor(PROVISIONAL_BATTLES), STANDARD_K_FACTOR);
+        assert_eq!(k_factor(u32::MAX), STANDARD_K_FACTOR);
+    }
+
+    #[test]
+    fn rating_deltas_for_equal_ratings() {
+        let provisional = (1200, 0);
+        let established = (1200, PROVISIONAL_BATTLES);
+        assert_eq!(rating_deltas(provisional, provisional, MatchOutcome::Win), (20, -20));
+        assert_eq!(rating_deltas(established, established, MatchOutcome::Loss), (-10, 10));
+        assert_eq!(rating_deltas(provisional, established, MatchOutcome::Win), (20, -10));
+        assert_eq!(rating_deltas(provisional, provisional, MatchOutcome::Draw), (0, 0));
+    }
+
+    #[test]
+    fn rating_deltas_for_draws_move_towards_the_underdog() {
+        let favourite = (1400, PROVISIONAL_BATTLES);
+        let underdog = (1200, PROVISIONAL_BATTLES);
+        assert_eq!(rating_deltas(favourite, underdog, MatchOutcome::Draw), (-5, 5));
+        assert_eq!(rating_deltas(underdog, favourite, MatchOutcome::Draw), (5, -5));
+    }
+
+    #[test]
+    fn rating_deltas_for_a_large_gap() {
+        let favourite = (2000, PROVISIONAL_BATTLES);
+        let underdog = (1000, PROVISIONAL_BATTLES);
+        assert_eq!(rating_deltas(favourite, underdog, MatchOutcome::Win), (0, 0));
+        assert_eq!(rating_deltas(favourite, underdog, MatchOutcome::Loss), (-20, 20));
+        assert_eq!(rating_deltas(underdog, favourite, MatchOutcome::Win), (20, -20));
+    }
+}
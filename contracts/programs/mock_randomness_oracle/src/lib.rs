@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+
+declare_id!("DCdbdtrYta1SkcYgFZrdaQMD1RLRWFPeAzN8ibwvvLiv");
+
+/// Local-only randomness oracle used by `anchor test` and `solana-program-test` suites.
+/// Writes caller-supplied seeds using the account layout battle_core expects from an oracle;
+/// it performs no verification and must never be configured on a public cluster.
+#[program]
+pub mod mock_randomness_oracle {
+    use super::*;
+
+    pub fn fulfill_randomness(
+        ctx: Context<FulfillRandomness>,
+        params: FulfillRandomnessParams,
+    ) -> Result<()> {
+        let randomness = &mut ctx.accounts.randomness;
+        randomness.battle = params.battle;
+        randomness.round = params.round;
+        randomness.seed = params.seed;
+        randomness.fulfilled = true;
+        let clock = Clock::get()?;
+        randomness.fulfilled_at = clock.unix_timestamp;
+        randomness.fulfilled_slot = clock.slot;
+        randomness.bump = ctx.bumps.randomness;
+        emit!(RandomnessFulfilled {
+            randomness: randomness.key(),
+            battle: params.battle,
+            round: params.round,
+            seed: params.seed,
+        });
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct FulfillRandomnessParams {
+    pub battle: Pubkey,
+    pub round: u8,
+    pub seed: [u8; 32],
+}
+
+#[derive(Accounts)]
+#[instruction(params: FulfillRandomnessParams)]
+pub struct FulfillRandomness<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [RandomnessAccount::SEED, params.battle.as_ref(), &[params.round]],
+        bump,
+        space = RandomnessAccount::space()
+    )]
+    pub randomness: Account<'info, RandomnessAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Field order mirrors `battle_core::OracleRandomness`; keep the prefix stable.
+#[account]
+pub struct RandomnessAccount {
+    pub battle: Pubkey,
+    pub round: u8,
+    pub seed: [u8; 32],
+    pub fulfilled: bool,
+    pub fulfilled_at: i64,
+    pub fulfilled_slot: u64,
+    pub bump: u8,
+}
+
+impl RandomnessAccount {
+    pub const SEED: &'static [u8] = b"randomness";
+
+    pub fn space() -> usize {
+        8 + 32 + 1 + 32 + 1 + 8 + 8 + 1
+    }
+}
+
+#[event]
+pub struct RandomnessFulfilled {
+    pub randomness: Pubkey,
+    pub battle: Pubkey,
+    pub round: u8,
+    pub seed: [u8; 32],
+}
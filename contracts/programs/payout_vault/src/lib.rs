@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
 use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
 
 declare_id!("PytVlt1111111111111111111111111111111111111111");
 
@@ -12,23 +15,47 @@ pub mod payout_vault {
         vault.authority = ctx.accounts.authority.key();
         vault.bump = ctx.bumps.vault_state;
         vault.paused = false;
+        vault.pauser = params.pauser;
+        vault.pause_reason = None;
+        vault.pending_authority = None;
+        vault.withdrawal_policy = WithdrawalPolicy::default();
+        vault.pending_withdrawal_policy = None;
+        vault.pending_policy_effective_at = 0;
+        vault.pending_withdrawal = None;
+        vault.withdrawal_epoch = 0;
+        vault.epoch_withdrawn = 0;
+        vault.total_withdrawn = 0;
+        vault.approvers = Vec::new();
+        vault.approval_threshold = 0;
+        vault.next_proposal_id = 0;
+        vault.fee_config = FeeConfig::default();
+        vault.pending_fee_config = None;
+        vault.pending_fee_effective_at = 0;
+        vault.total_fees = 0;
+        vault.total_house_paid_out = 0;
+        vault.expiry_policy = ExpiryPolicy::default();
+        vault.attester = None;
+        vault.total_frozen = 0;
+        vault.total_escrow_frozen = 0;
+        vault.total_frozen_released = 0;
         vault.total_deposited = 0;
         vault.total_locked = 0;
         vault.total_settled = 0;
         vault.matka_token_mint = params.matka_token_mint;
+        vault.battle_authority = params.battle_authority;
+        vault.battle_program = params.battle_program;
         emit!(VaultInitialized {
             vault: vault.key(),
             authority: vault.authority,
         });
-        // TODO(payout_vault MATKA): Wire up SPL MATKA token mint tracking once tokenomics settle.
         Ok(())
     }
 
     pub fn deposit_treasury(ctx: Context<DepositTreasury>, amount: u64) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
-        let vault = &mut ctx.accounts.vault_state;
         let from = ctx.accounts.treasury_funder.to_account_info();
         let to = ctx.accounts.vault_state.to_account_info();
+        let vault = &mut ctx.accounts.vault_state;
         let cpi_accounts = system_program::Transfer { from: from.clone(), to: to.clone() };
         let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
         system_program::transfer(cpi_ctx, amount)?;
@@ -40,6 +67,7 @@ pub mod payout_vault {
             vault: vault.key(),
             funder: ctx.accounts.treasury_funder.key(),
             amount,
+            mint: None,
         });
         Ok(())
     }
@@ -47,9 +75,68 @@ pub mod payout_vault {
     pub fn lock_wager(ctx: Context<LockWager>, params: LockWagerParams) -> Result<()> {
         require!(!ctx.accounts.vault_state.paused, VaultError::VaultPaused);
         require!(params.amount > 0, VaultError::InvalidAmount);
+        BattleSnapshot::load(&ctx.accounts.battle, &ctx.accounts.vault_state)?
+            .check_lock(&params)?;
+        let now = Clock::get()?.unix_timestamp;
+        let player_stake = match params.mode {
+            WagerMode::House => 0,
+            WagerMode::Escrow => params.amount,
+        };
+        DenyListEntry::ensure_not_denied(&ctx.accounts.primary_deny_entry)?;
+        PlayerLimitsSnapshot::ensure_not_excluded(
+            &ctx.accounts.primary_self_exclusion,
+            &ctx.accounts.vault_state,
+            now,
+        )?;
+        AttestationSnapshot::check(
+            ctx.accounts.primary_attestation.as_deref(),
+            &ctx.accounts.vault_state,
+            &params.primary_recipient,
+            now,
+        )?;
+        PlayerWagerLimits::update(&ctx.accounts.primary_limits, now, |limits| {
+            limits.record_stake(player_stake)
+        })?;
+        let vault_ai = ctx.accounts.vault_state.to_account_info();
         let vault = &mut ctx.accounts.vault_state;
-        let available = vault.available_funds(ctx.accounts.vault_state.to_account_info())?;
-        require!(available >= params.amount, VaultError::InsufficientVaultBalance);
+        match params.mode {
+            WagerMode::House => {
+                authorize_house_lock(
+                    vault,
+                    [
+                        Some(ctx.accounts.authority.key()),
+                        ctx.accounts.house_authority.as_ref().map(|signer| signer.key()),
+                    ],
+                    ctx.accounts.proposal.as_mut(),
+                    &ProposalAction::LockHouseWager {
+                        battle: ctx.accounts.battle.key(),
+                        amount: params.amount,
+                    },
+                )?;
+                let available = vault.available_funds(vault_ai)?;
+                require!(available >= params.amount, VaultError::InsufficientVaultBalance);
+                vault.total_locked = vault
+                    .total_locked
+                    .checked_add(params.amount)
+                    .ok_or(VaultError::Overflow)?;
+            }
+            WagerMode::Escrow => {
+                // Secondary stakes are deposited on assignment, so the recipient must be unset.
+                require!(params.secondary_recipient.is_none(), VaultError::InvalidRecipient);
+                let escrow = ctx.accounts.escrow.as_ref().ok_or(VaultError::EscrowAccountMissing)?;
+                require!(
+                    params.amount >= Rent::get()?.minimum_balance(0),
+                    VaultError::StakeBelowRentMinimum
+                );
+                let cpi_accounts = system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: escrow.to_account_info(),
+                };
+                let cpi_ctx =
+                    CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+                system_program::transfer(cpi_ctx, params.amount)?;
+            }
+        }
 
         let wager = &mut ctx.accounts.wager_state;
         wager.vault = vault.key();
@@ -58,14 +145,15 @@ pub mod payout_vault {
         wager.locked_amount = params.amount;
         wager.recipient_primary = params.primary_recipient;
         wager.recipient_secondary = params.secondary_recipient;
-        wager.locked_at = Clock::get()?.unix_timestamp;
+        wager.rent_payer = ctx.accounts.payer.key();
+        wager.locked_at = now;
         wager.settled_at = None;
         wager.settled = false;
-
-        vault.total_locked = vault
-            .total_locked
-            .checked_add(params.amount)
-            .ok_or(VaultError::Overflow)?;
+        wager.mode = params.mode;
+        wager.mint = None;
+        wager.escrow_bump = ctx.bumps.escrow;
+        wager.primary_stake = player_stake;
+        wager.secondary_stake = 0;
 
         emit!(WagerLocked {
             vault: vault.key(),
@@ -73,6 +161,8 @@ pub mod payout_vault {
             amount: params.amount,
             primary_recipient: params.primary_recipient,
             secondary_recipient: params.secondary_recipient,
+            mode: params.mode,
+            mint: None,
         });
 
         Ok(())
@@ -81,8 +171,25 @@ pub mod payout_vault {
     pub fn settle_payout(ctx: Context<SettlePayout>, params: SettlePayoutParams) -> Result<()> {
         require!(!ctx.accounts.vault_state.paused, VaultError::VaultPaused);
         let authority_key = ctx.accounts.authority.key();
-        require_keys_eq!(authority_key, ctx.accounts.vault_state.authority, VaultError::Unauthorized);
+        require!(ctx.accounts.vault_state.is_operator(&authority_key), VaultError::Unauthorized);
+
+        // Battle program CPIs settle automatically, within a reservation that was approved at
+        // lock time; manual settlements need multisig approval.
+        if Some(authority_key) != ctx.accounts.vault_state.battle_authority {
+            let action = ProposalAction::SettlePayout {
+                wager_state: ctx.accounts.wager_state.key(),
+                primary_amount: params.primary_amount,
+                secondary_amount: params.secondary_amount,
+            };
+            consume_proposal(&ctx.accounts.vault_state, ctx.accounts.proposal.as_mut(), &action)?;
+        }
+        BattleSnapshot::load(&ctx.accounts.battle, &ctx.accounts.vault_state)?.check_payout(
+            &ctx.accounts.wager_state,
+            params.primary_amount,
+            params.secondary_amount.unwrap_or(0),
+        )?;
 
+        let vault_ai = ctx.accounts.vault_state.to_account_info();
         let vault = &mut ctx.accounts.vault_state;
         let wager = &mut ctx.accounts.wager_state;
         require!(!wager.settled, VaultError::WagerAlreadySettled);
@@ -94,9 +201,77 @@ pub mod payout_vault {
             .ok_or(VaultError::Overflow)?;
         require!(total_payout <= wager.locked_amount, VaultError::SettlementExceedsLock);
 
-        let vault_ai = ctx.accounts.vault_state.to_account_info();
-        let seeds = &[VaultState::SEED, &[vault.bump]];
-        let signer_seeds = &[&seeds[..]];
+        let wager_key = wager.key();
+        vault.refresh_fee_config(Clock::get()?.unix_timestamp);
+        let escrow_bump = [wager.escrow_bump];
+        let escrow_seeds: &[&[u8]] = &[WagerState::ESCROW_SEED, wager_key.as_ref(), &escrow_bump];
+        let source = match wager.mode {
+            WagerMode::House => vault_ai.clone(),
+            WagerMode::Escrow => {
+                // Escrowed stakes must be released in full; a partial payout strands funds.
+                require!(
+                    total_payout == wager.locked_amount,
+                    VaultError::EscrowNotFullyReleased
+                );
+                let escrow =
+                    ctx.accounts.escrow.as_ref().ok_or(VaultError::EscrowAccountMissing)?;
+                escrow.to_account_info()
+            }
+        };
+        let signer_seeds = &[escrow_seeds];
+        let system_program_ai = ctx.accounts.system_program.to_account_info();
+        let mode = wager.mode;
+        // The system program cannot debit the vault account because it carries data, so house
+        // payouts and fees move the lamports directly; escrow ones are signed system transfers.
+        let pay = |destination, amount: u64| -> Result<()> {
+            match mode {
+                WagerMode::House => debit_vault(&source, &destination, amount),
+                WagerMode::Escrow => {
+                    let cpi_accounts = system_program::Transfer {
+                        from: source.clone(),
+                        to: destination,
+                    };
+                    let cpi_ctx = CpiContext::new_with_signer(
+                        system_program_ai.clone(),
+                        cpi_accounts,
+                        signer_seeds,
+                    );
+                    system_program::transfer(cpi_ctx, amount)
+                }
+            }
+        };
+
+        // Rake comes out of the requested payouts and goes to the configured fee recipient.
+        let fee = vault.fee_config.fee_for(wager, params.primary_amount, secondary_amount);
+        let (primary_net, secondary_net) = split_fee(params.primary_amount, secondary_amount, fee);
+        if fee > 0 {
+            let fee_recipient = ctx
+                .accounts
+                .fee_recipient
+                .as_ref()
+                .ok_or(VaultError::FeeRecipientMissing)?;
+            require!(
+                vault.fee_config.fee_recipient == Some(fee_recipient.key()),
+                VaultError::FeeRecipientMismatch
+            );
+            pay(fee_recipient.to_account_info(), fee)?;
+        }
+
+        // Shares owed to denied wallets are held in the vault pending review instead of paid.
+        let primary_frozen = DenyListEntry::hold(&ctx.accounts.primary_deny_entry, primary_net)?;
+        let secondary_frozen = match wager.recipient_secondary {
+            Some(_) => {
+                let entry = ctx
+                    .accounts
+                    .secondary_deny_entry
+                    .as_ref()
+                    .ok_or(VaultError::DenyListEntryMissing)?;
+                DenyListEntry::hold(entry, secondary_net)?
+            }
+            None => false,
+        };
+        // House-mode shares that freeze never leave the vault account.
+        let moves_funds = |frozen: bool| !frozen || wager.mode == WagerMode::Escrow;
 
         // Primary payout
         require_keys_eq!(
@@ -104,17 +279,12 @@ pub mod payout_vault {
             wager.recipient_primary,
             VaultError::InvalidRecipient
         );
-        if params.primary_amount > 0 {
-            let cpi_accounts = system_program::Transfer {
-                from: vault_ai.clone(),
-                to: ctx.accounts.primary_recipient.to_account_info(),
+        if primary_net > 0 && moves_funds(primary_frozen) {
+            let destination = match primary_frozen {
+                true => vault_ai.clone(),
+                false => ctx.accounts.primary_recipient.to_account_info(),
             };
-            let cpi_ctx = CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                cpi_accounts,
-                signer_seeds,
-            );
-            system_program::transfer(cpi_ctx, params.primary_amount)?;
+            pay(destination, primary_net)?;
         }
 
         // Secondary payout (optional)
@@ -123,251 +293,3533 @@ pub mod payout_vault {
                 .recipient_secondary
                 .ok_or(VaultError::InvalidRecipient)?;
             require_keys_eq!(recipient.key(), stored_secondary, VaultError::InvalidRecipient);
-            if secondary_amount > 0 {
-                let cpi_accounts = system_program::Transfer {
-                    from: vault_ai.clone(),
-                    to: recipient.to_account_info(),
+            if secondary_net > 0 && moves_funds(secondary_frozen) {
+                let destination = match secondary_frozen {
+                    true => vault_ai.clone(),
+                    false => recipient.to_account_info(),
                 };
-                let cpi_ctx = CpiContext::new_with_signer(
-                    ctx.accounts.system_program.to_account_info(),
-                    cpi_accounts,
-                    signer_seeds,
-                );
-                system_program::transfer(cpi_ctx, secondary_amount)?;
+                pay(destination, secondary_net)?;
             }
         } else {
             require!(wager.recipient_secondary.is_none(), VaultError::InvalidRecipient);
             require!(secondary_amount == 0, VaultError::SettlementSecondaryMismatch);
         }
 
+        let now = Clock::get()?.unix_timestamp;
         wager.settled = true;
-        wager.settled_at = Some(Clock::get()?.unix_timestamp);
+        wager.settled_at = Some(now);
 
-        vault.total_locked = vault
-            .total_locked
-            .checked_sub(wager.locked_amount)
+        // Only escrow wagers put player funds at risk, so only they move net losses.
+        if wager.mode == WagerMode::Escrow {
+            let results = [
+                (ctx.accounts.primary_limits.as_ref(), wager.primary_stake, primary_net),
+                (ctx.accounts.secondary_limits.as_ref(), wager.secondary_stake, secondary_net),
+            ];
+            for (limits_account, stake, returned) in results {
+                if stake == 0 {
+                    continue;
+                }
+                let limits_account = limits_account.ok_or(VaultError::PlayerLimitsMissing)?;
+                PlayerWagerLimits::update(limits_account, now, |limits| {
+                    limits.record_result(stake, returned)
+                })?;
+            }
+        }
+
+        let frozen_shares = [
+            (primary_frozen, wager.recipient_primary, primary_net),
+            (secondary_frozen, wager.recipient_secondary.unwrap_or_default(), secondary_net),
+        ];
+        let mut frozen = 0u64;
+        for (is_frozen, wallet, amount) in frozen_shares {
+            if !is_frozen {
+                continue;
+            }
+            frozen = frozen.checked_add(amount).ok_or(VaultError::Overflow)?;
+            emit!(PayoutFrozen {
+                vault: vault.key(),
+                battle: wager.battle,
+                wallet,
+                amount,
+            });
+        }
+        vault.total_frozen = vault
+            .total_frozen
+            .checked_add(frozen)
             .ok_or(VaultError::Overflow)?;
+
+        match wager.mode {
+            WagerMode::House => {
+                vault.total_locked = vault
+                    .total_locked
+                    .checked_sub(wager.locked_amount)
+                    .ok_or(VaultError::Overflow)?;
+                vault.total_house_paid_out = vault
+                    .total_house_paid_out
+                    .checked_add(total_payout - frozen)
+                    .ok_or(VaultError::Overflow)?;
+            }
+            WagerMode::Escrow => {
+                vault.total_escrow_frozen = vault
+                    .total_escrow_frozen
+                    .checked_add(frozen)
+                    .ok_or(VaultError::Overflow)?;
+            }
+        }
         vault.total_settled = vault
             .total_settled
-            .checked_add(total_payout)
+            .checked_add(total_payout - fee)
+            .ok_or(VaultError::Overflow)?;
+        vault.total_fees = vault
+            .total_fees
+            .checked_add(fee)
             .ok_or(VaultError::Overflow)?;
 
         emit!(PayoutSettled {
             vault: vault.key(),
             battle: wager.battle,
-            primary_amount: params.primary_amount,
-            secondary_amount,
+            primary_amount: primary_net,
+            secondary_amount: secondary_net,
+            mint: None,
+            fee,
         });
 
         Ok(())
     }
 
-    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>, amount: u64) -> Result<()> {
-        require!(amount > 0, VaultError::InvalidAmount);
-        let vault = &mut ctx.accounts.vault_state;
-        require_keys_eq!(ctx.accounts.authority.key(), vault.authority, VaultError::Unauthorized);
-
-        let available = vault.available_funds(ctx.accounts.vault_state.to_account_info())?;
-        require!(amount <= available, VaultError::InsufficientVaultBalance);
+    /// Unwinds a wager without a result: escrowed stakes go back to the stakers that
+    /// deposited them and house reservations are released. Only valid once the battle is
+    /// over without a recorded winner.
+    pub fn void_wager(ctx: Context<VoidWager>, reason: VoidReason) -> Result<()> {
+        BattleSnapshot::load(&ctx.accounts.battle, &ctx.accounts.vault_state)?.check_void()?;
+        require!(!ctx.accounts.wager_state.settled, VaultError::WagerAlreadySettled);
+        let refund = ctx.accounts.escrow_refund();
+        let wager = &mut ctx.accounts.wager_state;
+        let (primary_refund, secondary_refund) = unwind_wager(
+            wager,
+            &mut ctx.accounts.vault_state,
+            ctx.accounts.token_vault.as_mut(),
+            refund,
+        )?;
 
-        let vault_ai = ctx.accounts.vault_state.to_account_info();
-        let seeds = &[VaultState::SEED, &[vault.bump]];
-        let signer_seeds = &[&seeds[..]];
+        wager.settled = true;
+        wager.settled_at = Some(Clock::get()?.unix_timestamp);
 
-        let cpi_accounts = system_program::Transfer {
-            from: vault_ai.clone(),
-            to: ctx.accounts.destination.to_account_info(),
-        };
-        let cpi_ctx = CpiContext::new_with_signer(
-            ctx.accounts.system_program.to_account_info(),
-            cpi_accounts,
-            signer_seeds,
-        );
-        system_program::transfer(cpi_ctx, amount)?;
+        emit!(WagerVoided {
+            vault: wager.vault,
+            battle: wager.battle,
+            reason,
+            primary_refund,
+            secondary_refund,
+            mint: wager.mint,
+        });
+        Ok(())
+    }
 
-        emit!(EmergencyWithdrawal {
+    pub fn set_expiry_policy(ctx: Context<UpdateVaultConfig>, policy: ExpiryPolicy) -> Result<()> {
+        policy.validate()?;
+        let vault = &mut ctx.accounts.vault_state;
+        vault.expiry_policy = policy;
+        emit!(ExpiryPolicyUpdated {
             vault: vault.key(),
             authority: ctx.accounts.authority.key(),
-            amount,
+            max_lock_duration_secs: policy.max_lock_duration_secs,
+            crank_reward: policy.crank_reward,
         });
-
         Ok(())
     }
-}
-
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct InitializeVaultParams {
-    pub matka_token_mint: Option<Pubkey>,
-}
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct LockWagerParams {
-    pub amount: u64,
-    pub primary_recipient: Pubkey,
-    pub secondary_recipient: Option<Pubkey>,
-}
+    /// Permissionless crank that unwinds a wager locked for longer than the vault allows,
+    /// whatever state its battle is in; battle_core skips the vault on any exit taken after
+    /// the wager is gone. A wager whose battle account has been closed can be expired at
+    /// once, since no exit is left to settle it.
+    /// The cranker is paid out of the wager account's rent; the rest goes to the rent payer.
+    pub fn expire_wager(ctx: Context<ExpireWager>) -> Result<()> {
+        require!(!ctx.accounts.wager_state.settled, VaultError::WagerAlreadySettled);
+        let now = Clock::get()?.unix_timestamp;
+        let policy = ctx.accounts.vault_state.expiry_policy;
+        if !BattleSnapshot::is_closed(&ctx.accounts.battle) {
+            BattleSnapshot::load(&ctx.accounts.battle, &ctx.accounts.vault_state)?;
+            let expires_at = ctx
+                .accounts
+                .wager_state
+                .locked_at
+                .checked_add(policy.max_lock_duration_secs)
+                .ok_or(VaultError::Overflow)?;
+            require!(now >= expires_at, VaultError::WagerNotExpired);
+        }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct SettlePayoutParams {
-    pub primary_amount: u64,
-    pub secondary_amount: Option<u64>,
-}
+        let refund = ctx.accounts.escrow_refund();
+        let wager = &mut ctx.accounts.wager_state;
+        let (primary_refund, secondary_refund) = unwind_wager(
+            wager,
+            &mut ctx.accounts.vault_state,
+            ctx.accounts.token_vault.as_mut(),
+            refund,
+        )?;
+        wager.settled = true;
+        wager.settled_at = Some(now);
 
-#[derive(Accounts)]
-pub struct InitializeVault<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    #[account(
-        init,
-        payer = authority,
-        seeds = [VaultState::SEED],
-        bump,
-        space = VaultState::space()
-    )]
-    pub vault_state: Account<'info, VaultState>,
-    pub system_program: Program<'info, System>,
-}
+        let wager_ai = wager.to_account_info();
+        let crank_reward = policy.crank_reward.min(wager_ai.lamports());
+        **wager_ai.try_borrow_mut_lamports()? -= crank_reward;
+        **ctx.accounts.cranker.to_account_info().try_borrow_mut_lamports()? += crank_reward;
 
-#[derive(Accounts)]
-pub struct DepositTreasury<'info> {
-    #[account(mut)]
-    pub treasury_funder: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [VaultState::SEED],
-        bump = vault_state.bump
-    )]
-    pub vault_state: Account<'info, VaultState>,
-    pub system_program: Program<'info, System>,
-}
+        emit!(WagerExpired {
+            vault: wager.vault,
+            battle: wager.battle,
+            cranker: ctx.accounts.cranker.key(),
+            crank_reward,
+            primary_refund,
+            secondary_refund,
+            mint: wager.mint,
+        });
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct LockWager<'info> {
-    pub authority: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [VaultState::SEED],
-        bump = vault_state.bump,
-        constraint = vault_state.authority == authority.key() @ VaultError::Unauthorized
-    )]
-    pub vault_state: Account<'info, VaultState>,
-    #[account(
-        init,
-        payer = authority,
-        seeds = [
-            WagerState::SEED,
-            vault_state.key().as_ref(),
-            battle.key().as_ref()
-        ],
-        bump,
-        space = WagerState::space()
-    )]
-    pub wager_state: Account<'info, WagerState>,
-    /// CHECK: Battle account is stored for reference and PDA seeding; verification occurs off-chain.
-    pub battle: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Permissionless solvency check of the vault's lamport balance against its counters.
+    /// Pauses the vault if the balance no longer covers `total_locked`.
+    pub fn reconcile(ctx: Context<Reconcile>) -> Result<()> {
+        let vault_ai = ctx.accounts.vault_state.to_account_info();
+        let vault = &mut ctx.accounts.vault_state;
+        let rent = Rent::get()?.minimum_balance(VaultState::space());
+        let actual_balance = vault_ai.lamports();
+        let expected_balance = vault.expected_balance(rent);
+        let discrepancy = actual_balance as i128 - expected_balance as i128;
+        let under_collateralised = (actual_balance.saturating_sub(rent) as u128)
+            < vault.total_locked as u128 + vault.total_frozen as u128;
+        let now = Clock::get()?.unix_timestamp;
+        if under_collateralised && !vault.paused {
+            vault.paused = true;
+            vault.pause_reason = Some(PAUSE_REASON_UNDER_COLLATERALISED);
+            emit!(VaultPaused {
+                vault: vault.key(),
+                pauser: vault.key(),
+                reason_code: PAUSE_REASON_UNDER_COLLATERALISED,
+                paused_at: now,
+            });
+        }
+        emit!(VaultReconciled {
+            vault: vault.key(),
+            caller: ctx.accounts.caller.key(),
+            actual_balance,
+            expected_balance,
+            discrepancy: discrepancy.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+            total_locked: vault.total_locked,
+            under_collateralised,
+            reconciled_at: now,
+        });
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct SettlePayout<'info> {
-    pub authority: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [VaultState::SEED],
-        bump = vault_state.bump,
-        constraint = vault_state.authority == authority.key() @ VaultError::Unauthorized
-    )]
-    pub vault_state: Account<'info, VaultState>,
-    #[account(
-        mut,
-        seeds = [
-            WagerState::SEED,
-            vault_state.key().as_ref(),
-            wager_state.battle.as_ref()
-        ],
-        bump = wager_state.bump,
-        close = authority
-    )]
-    pub wager_state: Account<'info, WagerState>,
-    /// CHECK: Recipient validation occurs against stored keys before transfers.
-    #[account(mut)]
-    pub primary_recipient: UncheckedAccount<'info>,
-    /// CHECK: Secondary recipient is optional; validated if provided.
-    #[account(mut)]
-    pub secondary_recipient: Option<UncheckedAccount<'info>>,
-    pub system_program: Program<'info, System>,
-}
+    pub fn initialize_token_vault(ctx: Context<InitializeTokenVault>) -> Result<()> {
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.vault = ctx.accounts.vault_state.key();
+        token_vault.mint = ctx.accounts.mint.key();
+        token_vault.token_account = ctx.accounts.vault_token_account.key();
+        token_vault.bump = ctx.bumps.token_vault;
+        token_vault.total_deposited = 0;
+        token_vault.total_locked = 0;
+        token_vault.total_settled = 0;
+        token_vault.total_fees = 0;
+        token_vault.withdrawal_epoch_cap = 0;
+        token_vault.pending_epoch_cap = None;
+        token_vault.pending_cap_effective_at = 0;
+        token_vault.pending_withdrawal = None;
+        token_vault.withdrawal_epoch = 0;
+        token_vault.epoch_withdrawn = 0;
+        token_vault.total_withdrawn = 0;
+        token_vault.fee_cap = None;
+        token_vault.pending_fee_cap = None;
+        token_vault.pending_fee_cap_effective_at = 0;
+        emit!(TokenVaultInitialized {
+            vault: token_vault.vault,
+            mint: token_vault.mint,
+            token_account: token_vault.token_account,
+        });
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct EmergencyWithdraw<'info> {
-    pub authority: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [VaultState::SEED],
-        bump = vault_state.bump,
-        constraint = vault_state.authority == authority.key() @ VaultError::Unauthorized
-    )]
-    pub vault_state: Account<'info, VaultState>,
-    /// CHECK: Destination can be any system account controlled by the authority.
-    #[account(mut)]
-    pub destination: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
-}
+    pub fn deposit_treasury_token(ctx: Context<DepositTreasuryToken>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        let cpi_accounts = token::Transfer {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.treasury_funder.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.total_deposited = token_vault
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        emit!(TreasuryDeposited {
+            vault: token_vault.vault,
+            funder: ctx.accounts.treasury_funder.key(),
+            amount,
+            mint: Some(token_vault.mint),
+        });
+        Ok(())
+    }
 
-#[account]
-pub struct VaultState {
-    pub authority: Pubkey,
-    pub bump: u8,
-    pub paused: bool,
-    pub total_deposited: u64,
-    pub total_locked: u64,
-    pub total_settled: u64,
-    pub matka_token_mint: Option<Pubkey>,
-}
+    /// Token wagers are house-backed: the amount is reserved from the vault's token balance.
+    /// battle_core only locks lamport wagers, so token wagers are locked and settled directly by
+    /// the vault operators.
+    pub fn lock_wager_token(ctx: Context<LockWagerToken>, params: LockWagerParams) -> Result<()> {
+        require!(!ctx.accounts.vault_state.paused, VaultError::VaultPaused);
+        require!(params.amount > 0, VaultError::InvalidAmount);
+        BattleSnapshot::load(&ctx.accounts.battle, &ctx.accounts.vault_state)?
+            .check_lock(&params)?;
+        require!(params.mode == WagerMode::House, VaultError::TokenEscrowUnsupported);
+        authorize_house_lock(
+            &ctx.accounts.vault_state,
+            [
+                Some(ctx.accounts.authority.key()),
+                ctx.accounts.house_authority.as_ref().map(|signer| signer.key()),
+            ],
+            ctx.accounts.proposal.as_mut(),
+            &ProposalAction::LockHouseWager {
+                battle: ctx.accounts.battle.key(),
+                amount: params.amount,
+            },
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        DenyListEntry::ensure_not_denied(&ctx.accounts.primary_deny_entry)?;
+        PlayerLimitsSnapshot::ensure_not_excluded(
+            &ctx.accounts.primary_self_exclusion,
+            &ctx.accounts.vault_state,
+            now,
+        )?;
+        AttestationSnapshot::check(
+            ctx.accounts.primary_attestation.as_deref(),
+            &ctx.accounts.vault_state,
+            &params.primary_recipient,
+            now,
+        )?;
+        PlayerWagerLimits::update(&ctx.accounts.primary_limits, now, |limits| {
+            limits.record_stake(0)
+        })?;
+        let token_vault = &mut ctx.accounts.token_vault;
+        let available = token_vault.available_funds(&ctx.accounts.vault_token_account);
+        require!(available >= params.amount, VaultError::InsufficientVaultBalance);
+        token_vault.total_locked = token_vault
+            .total_locked
+            .checked_add(params.amount)
+            .ok_or(VaultError::Overflow)?;
 
-impl VaultState {
-    pub const SEED: &'static [u8] = b"vault-state";
+        let wager = &mut ctx.accounts.wager_state;
+        wager.vault = ctx.accounts.vault_state.key();
+        wager.battle = ctx.accounts.battle.key();
+        wager.bump = ctx.bumps.wager_state;
+        wager.locked_amount = params.amount;
+        wager.recipient_primary = params.primary_recipient;
+        wager.recipient_secondary = params.secondary_recipient;
+        wager.rent_payer = ctx.accounts.payer.key();
+        wager.locked_at = now;
+        wager.settled_at = None;
+        wager.settled = false;
+        wager.mode = WagerMode::House;
+        wager.mint = Some(token_vault.mint);
+        wager.escrow_bump = 0;
+        wager.primary_stake = 0;
+        wager.secondary_stake = 0;
 
-    pub fn space() -> usize {
-        8 + 32 + 1 + 1 + 8 + 8 + 8 + 1 + 32
+        emit!(WagerLocked {
+            vault: wager.vault,
+            battle: wager.battle,
+            amount: params.amount,
+            primary_recipient: params.primary_recipient,
+            secondary_recipient: params.secondary_recipient,
+            mode: WagerMode::House,
+            mint: wager.mint,
+        });
+        Ok(())
     }
 
-    pub fn available_funds(&self, vault_account: AccountInfo<'_>) -> Result<u64> {
-        let rent = Rent::get()?.minimum_balance(Self::space());
-        let balance = vault_account
-            .lamports()
-            .saturating_sub(rent);
-        Ok(balance.saturating_sub(self.total_locked))
-    }
+    pub fn settle_payout_token(
+        ctx: Context<SettlePayoutToken>,
+        params: SettlePayoutParams,
+    ) -> Result<()> {
+        require!(!ctx.accounts.vault_state.paused, VaultError::VaultPaused);
+        let authority_key = ctx.accounts.authority.key();
+        if Some(authority_key) != ctx.accounts.vault_state.battle_authority {
+            let action = ProposalAction::SettlePayout {
+                wager_state: ctx.accounts.wager_state.key(),
+                primary_amount: params.primary_amount,
+                secondary_amount: params.secondary_amount,
+            };
+            consume_proposal(&ctx.accounts.vault_state, ctx.accounts.proposal.as_mut(), &action)?;
+        }
+        BattleSnapshot::load(&ctx.accounts.battle, &ctx.accounts.vault_state)?.check_payout(
+            &ctx.accounts.wager_state,
+            params.primary_amount,
+            params.secondary_amount.unwrap_or(0),
+        )?;
+
+        // Token payouts cannot be frozen, so denied recipients block the settlement.
+        DenyListEntry::ensure_not_denied(&ctx.accounts.primary_deny_entry)?;
+        if ctx.accounts.wager_state.recipient_secondary.is_some() {
+            let entry = ctx
+                .accounts
+                .secondary_deny_entry
+                .as_ref()
+                .ok_or(VaultError::DenyListEntryMissing)?;
+            DenyListEntry::ensure_not_denied(entry)?;
+        }
+
+        let wager = &mut ctx.accounts.wager_state;
+        require!(!wager.settled, VaultError::WagerAlreadySettled);
+        let secondary_amount = params.secondary_amount.unwrap_or(0);
+        let total_payout = params
+            .primary_amount
+            .checked_add(secondary_amount)
+            .ok_or(VaultError::Overflow)?;
+        require!(total_payout <= wager.locked_amount, VaultError::SettlementExceedsLock);
+
+        let vault_bump = [ctx.accounts.vault_state.bump];
+        let vault_seeds: &[&[u8]] = &[VaultState::SEED, &vault_bump];
+        let signer_seeds = &[vault_seeds];
+        let vault_authority = ctx.accounts.vault_state.to_account_info();
+        let vault_token_account = ctx.accounts.vault_token_account.to_account_info();
+        let token_program = ctx.accounts.token_program.to_account_info();
+
+        // Lamport caps mean nothing for MATKA; each token vault carries its own fee cap.
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.vault_state.refresh_fee_config(now);
+        ctx.accounts.token_vault.refresh_fee_cap(now);
+        let fee_config = FeeConfig {
+            fee_cap: ctx.accounts.token_vault.fee_cap,
+            ..ctx.accounts.vault_state.fee_config
+        };
+        let fee = fee_config.fee_for(wager, params.primary_amount, secondary_amount);
+        let (primary_net, secondary_net) = split_fee(params.primary_amount, secondary_amount, fee);
+        if fee > 0 {
+            let fee_recipient = ctx
+                .accounts
+                .fee_recipient_token
+                .as_ref()
+                .ok_or(VaultError::FeeRecipientMissing)?;
+            require!(
+                fee_config.fee_recipient == Some(fee_recipient.owner),
+                VaultError::FeeRecipientMismatch
+            );
+            let cpi_accounts = token::Transfer {
+                from: vault_token_account.clone(),
+                to: fee_recipient.to_account_info(),
+                authority: vault_authority.clone(),
+            };
+            let cpi_ctx =
+                CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer_seeds);
+            token::transfer(cpi_ctx, fee)?;
+        }
+
+        require_keys_eq!(
+            ctx.accounts.primary_recipient_token.owner,
+            wager.recipient_primary,
+            VaultError::InvalidRecipient
+        );
+        if primary_net > 0 {
+            let cpi_accounts = token::Transfer {
+                from: vault_token_account.clone(),
+                to: ctx.accounts.primary_recipient_token.to_account_info(),
+                authority: vault_authority.clone(),
+            };
+            let cpi_ctx =
+                CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer_seeds);
+            token::transfer(cpi_ctx, primary_net)?;
+        }
+
+        if let Some(recipient) = ctx.accounts.secondary_recipient_token.as_ref() {
+            let stored_secondary = wager
+                .recipient_secondary
+                .ok_or(VaultError::InvalidRecipient)?;
+            require_keys_eq!(recipient.owner, stored_secondary, VaultError::InvalidRecipient);
+            if secondary_net > 0 {
+                let cpi_accounts = token::Transfer {
+                    from: vault_token_account.clone(),
+                    to: recipient.to_account_info(),
+                    authority: vault_authority.clone(),
+                };
+                let cpi_ctx =
+                    CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer_seeds);
+                token::transfer(cpi_ctx, secondary_net)?;
+            }
+        } else {
+            require!(wager.recipient_secondary.is_none(), VaultError::InvalidRecipient);
+            require!(secondary_amount == 0, VaultError::SettlementSecondaryMismatch);
+        }
+
+        wager.settled = true;
+        wager.settled_at = Some(Clock::get()?.unix_timestamp);
+
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.total_locked = token_vault
+            .total_locked
+            .checked_sub(wager.locked_amount)
+            .ok_or(VaultError::Overflow)?;
+        token_vault.total_settled = token_vault
+            .total_settled
+            .checked_add(total_payout - fee)
+            .ok_or(VaultError::Overflow)?;
+        token_vault.total_fees = token_vault
+            .total_fees
+            .checked_add(fee)
+            .ok_or(VaultError::Overflow)?;
+
+        emit!(PayoutSettled {
+            vault: token_vault.vault,
+            battle: wager.battle,
+            primary_amount: primary_net,
+            secondary_amount: secondary_net,
+            mint: Some(token_vault.mint),
+            fee,
+        });
+
+        Ok(())
+    }
+
+    pub fn assign_secondary_recipient(
+        ctx: Context<AssignSecondaryRecipient>,
+        recipient: Pubkey,
+    ) -> Result<()> {
+        BattleSnapshot::load(&ctx.accounts.battle, &ctx.accounts.vault_state)?
+            .check_secondary_recipient(&recipient)?;
+        let wager = &mut ctx.accounts.wager_state;
+        require!(!wager.settled, VaultError::WagerAlreadySettled);
+        require!(wager.recipient_secondary.is_none(), VaultError::SecondaryRecipientAlreadySet);
+        require_keys_neq!(recipient, wager.recipient_primary, VaultError::InvalidRecipient);
+        let player_stake = match wager.mode {
+            WagerMode::House => 0,
+            WagerMode::Escrow => wager.primary_stake,
+        };
+        let now = Clock::get()?.unix_timestamp;
+        DenyListEntry::ensure_not_denied(&ctx.accounts.secondary_deny_entry)?;
+        PlayerLimitsSnapshot::ensure_not_excluded(
+            &ctx.accounts.secondary_self_exclusion,
+            &ctx.accounts.vault_state,
+            now,
+        )?;
+        AttestationSnapshot::check(
+            ctx.accounts.secondary_attestation.as_deref(),
+            &ctx.accounts.vault_state,
+            &recipient,
+            now,
+        )?;
+        PlayerWagerLimits::update(&ctx.accounts.secondary_limits, now, |limits| {
+            limits.record_stake(player_stake)
+        })?;
+        if wager.mode == WagerMode::Escrow {
+            require!(!ctx.accounts.vault_state.paused, VaultError::VaultPaused);
+            let (staker, escrow, system_program) = match (
+                ctx.accounts.staker.as_ref(),
+                ctx.accounts.escrow.as_ref(),
+                ctx.accounts.system_program.as_ref(),
+            ) {
+                (Some(staker), Some(escrow), Some(system_program)) => {
+                    (staker, escrow, system_program)
+                }
+                _ => return Err(VaultError::EscrowAccountMissing.into()),
+            };
+            require_keys_eq!(staker.key(), recipient, VaultError::InvalidRecipient);
+            let cpi_accounts = system_program::Transfer {
+                from: staker.to_account_info(),
+                to: escrow.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(system_program.to_account_info(), cpi_accounts);
+            system_program::transfer(cpi_ctx, wager.primary_stake)?;
+            wager.secondary_stake = wager.primary_stake;
+            wager.locked_amount = wager
+                .locked_amount
+                .checked_add(wager.primary_stake)
+                .ok_or(VaultError::Overflow)?;
+        }
+        wager.recipient_secondary = Some(recipient);
+        emit!(SecondaryRecipientAssigned {
+            vault: ctx.accounts.vault_state.key(),
+            battle: wager.battle,
+            recipient,
+            stake: wager.secondary_stake,
+        });
+        Ok(())
+    }
+
+    pub fn add_to_deny_list(
+        ctx: Context<AddDenyListEntry>,
+        wallet: Pubkey,
+        reason_code: u16,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let entry = &mut ctx.accounts.deny_entry;
+        entry.vault = ctx.accounts.vault_state.key();
+        entry.wallet = wallet;
+        entry.bump = ctx.bumps.deny_entry;
+        entry.reason_code = reason_code;
+        entry.added_by = ctx.accounts.authority.key();
+        entry.added_at = now;
+        entry.frozen_amount = 0;
+        emit!(DenyListUpdated {
+            vault: entry.vault,
+            wallet,
+            denied: true,
+            reason_code,
+            authority: entry.added_by,
+        });
+        Ok(())
+    }
+
+    /// Lifts a denial. Any frozen payouts must be resolved first.
+    pub fn remove_from_deny_list(ctx: Context<RemoveDenyListEntry>) -> Result<()> {
+        let entry = &ctx.accounts.deny_entry;
+        require!(entry.frozen_amount == 0, VaultError::FrozenFundsOutstanding);
+        emit!(DenyListUpdated {
+            vault: entry.vault,
+            wallet: entry.wallet,
+            denied: false,
+            reason_code: entry.reason_code,
+            authority: ctx.accounts.authority.key(),
+        });
+        Ok(())
+    }
+
+    /// Pays out or forfeits to the treasury everything frozen for a denied wallet.
+    pub fn resolve_frozen_funds(
+        ctx: Context<ResolveFrozenFunds>,
+        resolution: FrozenFundsResolution,
+    ) -> Result<()> {
+        let vault_ai = ctx.accounts.vault_state.to_account_info();
+        let vault = &mut ctx.accounts.vault_state;
+        let entry = &mut ctx.accounts.deny_entry;
+        let action = ProposalAction::ResolveFrozenFunds {
+            wallet: entry.wallet,
+            resolution,
+        };
+        consume_proposal(vault, ctx.accounts.proposal.as_mut(), &action)?;
+        vault.refresh_withdrawal_policy(Clock::get()?.unix_timestamp);
+        let amount = entry.frozen_amount;
+        require!(amount > 0, VaultError::NoFrozenFunds);
+        let expected_destination = match resolution {
+            FrozenFundsResolution::Release => entry.wallet,
+            FrozenFundsResolution::Forfeit => vault
+                .withdrawal_policy
+                .treasury_destination
+                .ok_or(VaultError::TreasuryDestinationNotSet)?,
+        };
+        require_keys_eq!(
+            ctx.accounts.destination.key(),
+            expected_destination,
+            VaultError::InvalidRecipient
+        );
+
+        // Frozen escrow shares were swept into the vault account, so both kinds leave from there.
+        debit_vault(&vault_ai, &ctx.accounts.destination, amount)?;
+
+        entry.frozen_amount = 0;
+        vault.total_frozen = vault
+            .total_frozen
+            .checked_sub(amount)
+            .ok_or(VaultError::Overflow)?;
+        vault.total_frozen_released = vault
+            .total_frozen_released
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        emit!(FrozenFundsResolved {
+            vault: vault.key(),
+            wallet: entry.wallet,
+            resolution,
+            destination: expected_destination,
+            amount,
+        });
+        Ok(())
+    }
+
+    pub fn initialize_wager_limits(
+        ctx: Context<InitializeWagerLimits>,
+        limits: LimitSchedule,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let player_limits = &mut ctx.accounts.player_limits;
+        player_limits.vault = ctx.accounts.vault_state.key();
+        player_limits.player = ctx.accounts.player.key();
+        player_limits.bump = ctx.bumps.player_limits;
+        player_limits.limits = limits;
+        player_limits.pending_limits = None;
+        player_limits.pending_effective_at = 0;
+        player_limits.usage = [PeriodUsage {
+            window_start: now,
+            staked: 0,
+            net_loss: 0,
+        }; LIMIT_PERIOD_SECS.len()];
+        player_limits.created_at = now;
+        player_limits.updated_at = now;
+        emit!(WagerLimitsUpdated {
+            vault: player_limits.vault,
+            player: player_limits.player,
+            limits,
+            pending_limits: None,
+            pending_effective_at: 0,
+        });
+        Ok(())
+    }
+
+    /// Tighter limits apply immediately. Anything looser is queued and only replaces the
+    /// current limits after [`LIMIT_INCREASE_COOLING_SECS`].
+    pub fn set_wager_limits(ctx: Context<SetWagerLimits>, limits: LimitSchedule) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let player_limits = &mut ctx.accounts.player_limits;
+        player_limits.request_limits(limits, now)?;
+        emit!(WagerLimitsUpdated {
+            vault: player_limits.vault,
+            player: player_limits.player,
+            limits: player_limits.limits,
+            pending_limits: player_limits.pending_limits,
+            pending_effective_at: player_limits.pending_effective_at,
+        });
+        Ok(())
+    }
+
+    pub fn propose_authority_transfer(
+        ctx: Context<UpdateGovernedConfig>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault_state;
+        let action = ProposalAction::TransferAuthority { new_authority };
+        consume_proposal(vault, ctx.accounts.proposal.as_mut(), &action)?;
+        require!(
+            new_authority != vault.authority && new_authority != Pubkey::default(),
+            VaultError::InvalidPendingAuthority
+        );
+        vault.pending_authority = Some(new_authority);
+        emit!(AuthorityTransferProposed {
+            vault: vault.key(),
+            authority: vault.authority,
+            pending_authority: new_authority,
+        });
+        Ok(())
+    }
+
+    pub fn cancel_authority_transfer(ctx: Context<UpdateVaultConfig>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault_state;
+        let pending_authority = vault
+            .pending_authority
+            .take()
+            .ok_or(VaultError::NoPendingAuthority)?;
+        emit!(AuthorityTransferCancelled {
+            vault: vault.key(),
+            authority: vault.authority,
+            pending_authority,
+        });
+        Ok(())
+    }
+
+    pub fn accept_authority_transfer(ctx: Context<AcceptVaultAuthority>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault_state;
+        let previous_authority = vault.authority;
+        vault.authority = ctx.accounts.new_authority.key();
+        vault.pending_authority = None;
+        emit!(AuthorityTransferAccepted {
+            vault: vault.key(),
+            previous_authority,
+            new_authority: vault.authority,
+        });
+        Ok(())
+    }
+
+    pub fn set_pauser(ctx: Context<UpdateGovernedConfig>, pauser: Option<Pubkey>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault_state;
+        let action = ProposalAction::SetPauser { pauser };
+        consume_proposal(vault, ctx.accounts.proposal.as_mut(), &action)?;
+        vault.pauser = pauser;
+        emit!(PauserUpdated {
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            pauser,
+        });
+        Ok(())
+    }
+
+    pub fn set_attester(ctx: Context<UpdateVaultConfig>, attester: Option<Pubkey>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault_state;
+        vault.attester = attester;
+        emit!(AttesterUpdated {
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            attester,
+        });
+        Ok(())
+    }
+
+    pub fn pause_vault(ctx: Context<SetVaultPause>, reason_code: u16) -> Result<()> {
+        let vault = &mut ctx.accounts.vault_state;
+        require!(!vault.paused, VaultError::VaultPaused);
+        vault.paused = true;
+        vault.pause_reason = Some(reason_code);
+        emit!(VaultPaused {
+            vault: vault.key(),
+            pauser: ctx.accounts.pauser.key(),
+            reason_code,
+            paused_at: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn unpause_vault(ctx: Context<SetVaultPause>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault_state;
+        require!(vault.paused, VaultError::VaultNotPaused);
+        vault.paused = false;
+        vault.pause_reason = None;
+        emit!(VaultUnpaused {
+            vault: vault.key(),
+            pauser: ctx.accounts.pauser.key(),
+            unpaused_at: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Bootstraps the approver set. Once a threshold is active, changes go through proposals.
+    pub fn set_approvers(
+        ctx: Context<UpdateVaultConfig>,
+        approvers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault_state;
+        require!(!vault.multisig_enabled(), VaultError::ProposalRequired);
+        apply_approvers(vault, approvers, threshold)
+    }
+
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: ProposalAction) -> Result<()> {
+        action.validate()?;
+        let vault = &mut ctx.accounts.vault_state;
+        let proposer = ctx.accounts.proposer.key();
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.vault = vault.key();
+        proposal.proposal_id = vault.next_proposal_id;
+        proposal.bump = ctx.bumps.proposal;
+        proposal.proposer = proposer;
+        proposal.action = action;
+        // The proposer's signature counts as the first approval.
+        proposal.approvals = vec![proposer];
+        proposal.executed = false;
+        proposal.created_at = Clock::get()?.unix_timestamp;
+        vault.next_proposal_id = vault
+            .next_proposal_id
+            .checked_add(1)
+            .ok_or(VaultError::Overflow)?;
+        emit!(ProposalCreated {
+            vault: vault.key(),
+            proposal: proposal.key(),
+            proposal_id: proposal.proposal_id,
+            proposer,
+        });
+        Ok(())
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        let approver = ctx.accounts.approver.key();
+        let proposal = &mut ctx.accounts.proposal;
+        require!(!proposal.executed, VaultError::ProposalAlreadyExecuted);
+        require!(
+            !proposal.approvals.contains(&approver),
+            VaultError::ProposalAlreadyApproved
+        );
+        proposal.approvals.push(approver);
+        emit!(ProposalApproved {
+            vault: proposal.vault,
+            proposal: proposal.key(),
+            approver,
+            approvals: ctx.accounts.vault_state.approval_count(proposal),
+        });
+        Ok(())
+    }
+
+    pub fn execute_approver_change(ctx: Context<ExecuteApproverChange>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault_state;
+        let action = ctx.accounts.proposal.action.clone();
+        let (approvers, threshold) = match &action {
+            ProposalAction::SetApprovers { approvers, threshold } => {
+                (approvers.clone(), *threshold)
+            }
+            _ => return err!(VaultError::ProposalActionMismatch),
+        };
+        consume_proposal(vault, Some(&mut ctx.accounts.proposal), &action)?;
+        apply_approvers(vault, approvers, threshold)
+    }
+
+    /// Lowering the rake applies at once; a higher rate or cap, or a new recipient, waits out the
+    /// withdrawal delay so holders can react before more of each pot is redirected.
+    pub fn set_fee_config(ctx: Context<UpdateGovernedConfig>, fee_config: FeeConfig) -> Result<()> {
+        fee_config.validate()?;
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault_state;
+        let action = ProposalAction::SetFeeConfig { fee_config };
+        consume_proposal(vault, ctx.accounts.proposal.as_mut(), &action)?;
+        vault.refresh_withdrawal_policy(now);
+        vault.refresh_fee_config(now);
+        let current = vault.fee_config;
+        vault.fee_config = current.tightest(&fee_config);
+        if fee_config.loosens(&current) {
+            vault.pending_fee_config = Some(fee_config);
+            vault.pending_fee_effective_at = now
+                .checked_add(vault.withdrawal_policy.delay_secs)
+                .ok_or(VaultError::Overflow)?;
+        } else {
+            vault.pending_fee_config = None;
+            vault.pending_fee_effective_at = 0;
+        }
+        let active = vault.fee_config;
+        emit!(FeeConfigUpdated {
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            fee_bps: active.fee_bps,
+            fee_cap: active.fee_cap,
+            fee_recipient: active.fee_recipient,
+            pending_fee_config: vault.pending_fee_config,
+            pending_effective_at: vault.pending_fee_effective_at,
+        });
+        Ok(())
+    }
+
+    /// Per-settlement fee ceiling for this token vault, in token units. Raising it is queued
+    /// behind the withdrawal delay like any other fee loosening.
+    pub fn set_token_fee_cap(
+        ctx: Context<UpdateTokenVaultGovernedConfig>,
+        fee_cap: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault_state;
+        let token_vault = &mut ctx.accounts.token_vault;
+        let action = ProposalAction::SetTokenFeeCap {
+            mint: token_vault.mint,
+            fee_cap,
+        };
+        consume_proposal(vault, ctx.accounts.proposal.as_mut(), &action)?;
+        vault.refresh_withdrawal_policy(now);
+        token_vault.refresh_fee_cap(now);
+        if fee_cap <= token_vault.fee_cap.unwrap_or(u64::MAX) {
+            token_vault.fee_cap = Some(fee_cap);
+            token_vault.pending_fee_cap = None;
+            token_vault.pending_fee_cap_effective_at = 0;
+        } else {
+            token_vault.pending_fee_cap = Some(fee_cap);
+            token_vault.pending_fee_cap_effective_at = now
+                .checked_add(vault.withdrawal_policy.delay_secs)
+                .ok_or(VaultError::Overflow)?;
+        }
+        emit!(TokenFeeCapUpdated {
+            vault: vault.key(),
+            mint: token_vault.mint,
+            authority: ctx.accounts.authority.key(),
+            fee_cap: token_vault.fee_cap,
+            pending_fee_cap: token_vault.pending_fee_cap,
+            pending_effective_at: token_vault.pending_fee_cap_effective_at,
+        });
+        Ok(())
+    }
+
+    pub fn set_withdrawal_policy(
+        ctx: Context<UpdateGovernedConfig>,
+        policy: WithdrawalPolicy,
+    ) -> Result<()> {
+        policy.validate()?;
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault_state;
+        let action = ProposalAction::SetWithdrawalPolicy { policy };
+        consume_proposal(vault, ctx.accounts.proposal.as_mut(), &action)?;
+        // Swapping the destination or delay mid-queue would sidestep the timelock.
+        require!(vault.pending_withdrawal.is_none(), VaultError::WithdrawalAlreadyPending);
+        vault.refresh_withdrawal_policy(now);
+        // Tightening applies at once; anything looser waits out the current delay.
+        let current = vault.withdrawal_policy;
+        vault.withdrawal_policy = current.tightest(&policy);
+        if policy.loosens(&current) {
+            vault.pending_withdrawal_policy = Some(policy);
+            vault.pending_policy_effective_at = now
+                .checked_add(current.delay_secs)
+                .ok_or(VaultError::Overflow)?;
+        } else {
+            vault.pending_withdrawal_policy = None;
+            vault.pending_policy_effective_at = 0;
+        }
+        let active = vault.withdrawal_policy;
+        emit!(WithdrawalPolicyUpdated {
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            treasury_destination: active.treasury_destination,
+            delay_secs: active.delay_secs,
+            epoch_cap: active.epoch_cap,
+            pending_policy: vault.pending_withdrawal_policy,
+            pending_effective_at: vault.pending_policy_effective_at,
+        });
+        Ok(())
+    }
+
+    pub fn queue_emergency_withdraw(ctx: Context<UpdateVaultConfig>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault_state;
+        vault.refresh_withdrawal_policy(now);
+        let policy = vault.withdrawal_policy;
+        require!(
+            policy.treasury_destination.is_some(),
+            VaultError::TreasuryDestinationNotSet
+        );
+        require!(vault.pending_withdrawal.is_none(), VaultError::WithdrawalAlreadyPending);
+        require!(amount <= policy.epoch_cap, VaultError::WithdrawalCapExceeded);
+        let executable_at = now
+            .checked_add(policy.delay_secs)
+            .ok_or(VaultError::Overflow)?;
+        vault.pending_withdrawal = Some(PendingWithdrawal {
+            amount,
+            requested_at: now,
+            executable_at,
+        });
+        emit!(EmergencyWithdrawalQueued {
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            amount,
+            executable_at,
+            mint: None,
+        });
+        Ok(())
+    }
+
+    /// The pauser can veto a queued withdrawal too, so a compromised authority cannot drain funds.
+    pub fn cancel_emergency_withdraw(ctx: Context<SetVaultPause>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault_state;
+        let pending = vault
+            .pending_withdrawal
+            .take()
+            .ok_or(VaultError::NoPendingWithdrawal)?;
+        emit!(EmergencyWithdrawalCancelled {
+            vault: vault.key(),
+            cancelled_by: ctx.accounts.pauser.key(),
+            amount: pending.amount,
+            mint: None,
+        });
+        Ok(())
+    }
+
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>) -> Result<()> {
+        require!(!ctx.accounts.vault_state.paused, VaultError::VaultPaused);
+        let vault_ai = ctx.accounts.vault_state.to_account_info();
+        let vault = &mut ctx.accounts.vault_state;
+        let pending = vault.pending_withdrawal.ok_or(VaultError::NoPendingWithdrawal)?;
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp >= pending.executable_at,
+            VaultError::WithdrawalTimelockActive
+        );
+        let amount = pending.amount;
+        let action = ProposalAction::EmergencyWithdraw {
+            amount,
+            destination: ctx.accounts.destination.key(),
+        };
+        consume_proposal(vault, ctx.accounts.proposal.as_mut(), &action)?;
+
+        if vault.withdrawal_epoch != clock.epoch {
+            vault.withdrawal_epoch = clock.epoch;
+            vault.epoch_withdrawn = 0;
+        }
+        let epoch_withdrawn = vault
+            .epoch_withdrawn
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        require!(
+            epoch_withdrawn <= vault.withdrawal_policy.epoch_cap,
+            VaultError::WithdrawalCapExceeded
+        );
+
+        let available = vault.available_funds(vault_ai.clone())?;
+        require!(amount <= available, VaultError::InsufficientVaultBalance);
+        debit_vault(&vault_ai, &ctx.accounts.destination, amount)?;
+
+        vault.pending_withdrawal = None;
+        vault.epoch_withdrawn = epoch_withdrawn;
+        vault.total_withdrawn = vault
+            .total_withdrawn
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+
+        emit!(EmergencyWithdrawal {
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            amount,
+            mint: None,
+        });
+
+        Ok(())
+    }
+
+    /// Per-epoch cap for MATKA withdrawals. Lowering it applies immediately; raising it is queued
+    /// behind the withdrawal policy delay like any other loosening.
+    pub fn set_token_withdrawal_cap(
+        ctx: Context<UpdateTokenVaultGovernedConfig>,
+        epoch_cap: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault_state;
+        let token_vault = &mut ctx.accounts.token_vault;
+        let action = ProposalAction::SetTokenWithdrawalCap {
+            mint: token_vault.mint,
+            epoch_cap,
+        };
+        consume_proposal(vault, ctx.accounts.proposal.as_mut(), &action)?;
+        vault.refresh_withdrawal_policy(now);
+        token_vault.refresh_withdrawal_cap(now);
+
+        let current = token_vault.withdrawal_epoch_cap;
+        token_vault.withdrawal_epoch_cap = current.min(epoch_cap);
+        if epoch_cap > current {
+            token_vault.pending_epoch_cap = Some(epoch_cap);
+            token_vault.pending_cap_effective_at = now
+                .checked_add(vault.withdrawal_policy.delay_secs)
+                .ok_or(VaultError::Overflow)?;
+        } else {
+            token_vault.pending_epoch_cap = None;
+            token_vault.pending_cap_effective_at = 0;
+        }
+        emit!(TokenWithdrawalCapUpdated {
+            vault: vault.key(),
+            mint: token_vault.mint,
+            authority: ctx.accounts.authority.key(),
+            epoch_cap: token_vault.withdrawal_epoch_cap,
+            pending_epoch_cap: token_vault.pending_epoch_cap,
+            pending_effective_at: token_vault.pending_cap_effective_at,
+        });
+        Ok(())
+    }
+
+    pub fn queue_token_withdraw(ctx: Context<UpdateTokenVaultConfig>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault_state;
+        vault.refresh_withdrawal_policy(now);
+        let policy = vault.withdrawal_policy;
+        require!(
+            policy.treasury_destination.is_some(),
+            VaultError::TreasuryDestinationNotSet
+        );
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.refresh_withdrawal_cap(now);
+        require!(
+            token_vault.pending_withdrawal.is_none(),
+            VaultError::WithdrawalAlreadyPending
+        );
+        require!(
+            amount <= token_vault.withdrawal_epoch_cap,
+            VaultError::WithdrawalCapExceeded
+        );
+        let executable_at = now
+            .checked_add(policy.delay_secs)
+            .ok_or(VaultError::Overflow)?;
+        token_vault.pending_withdrawal = Some(PendingWithdrawal {
+            amount,
+            requested_at: now,
+            executable_at,
+        });
+        emit!(EmergencyWithdrawalQueued {
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            amount,
+            executable_at,
+            mint: Some(token_vault.mint),
+        });
+        Ok(())
+    }
+
+    pub fn cancel_token_withdraw(ctx: Context<CancelTokenWithdraw>) -> Result<()> {
+        let token_vault = &mut ctx.accounts.token_vault;
+        let pending = token_vault
+            .pending_withdrawal
+            .take()
+            .ok_or(VaultError::NoPendingWithdrawal)?;
+        emit!(EmergencyWithdrawalCancelled {
+            vault: ctx.accounts.vault_state.key(),
+            cancelled_by: ctx.accounts.pauser.key(),
+            amount: pending.amount,
+            mint: Some(token_vault.mint),
+        });
+        Ok(())
+    }
+
+    pub fn withdraw_treasury_token(ctx: Context<WithdrawTreasuryToken>) -> Result<()> {
+        require!(!ctx.accounts.vault_state.paused, VaultError::VaultPaused);
+        let clock = Clock::get()?;
+        let token_vault = &mut ctx.accounts.token_vault;
+        let pending = token_vault
+            .pending_withdrawal
+            .ok_or(VaultError::NoPendingWithdrawal)?;
+        require!(
+            clock.unix_timestamp >= pending.executable_at,
+            VaultError::WithdrawalTimelockActive
+        );
+        let amount = pending.amount;
+        let action = ProposalAction::WithdrawToken {
+            mint: token_vault.mint,
+            amount,
+            destination: ctx.accounts.destination.key(),
+        };
+        consume_proposal(
+            &ctx.accounts.vault_state,
+            ctx.accounts.proposal.as_mut(),
+            &action,
+        )?;
+
+        token_vault.refresh_withdrawal_cap(clock.unix_timestamp);
+        if token_vault.withdrawal_epoch != clock.epoch {
+            token_vault.withdrawal_epoch = clock.epoch;
+            token_vault.epoch_withdrawn = 0;
+        }
+        let epoch_withdrawn = token_vault
+            .epoch_withdrawn
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        require!(
+            epoch_withdrawn <= token_vault.withdrawal_epoch_cap,
+            VaultError::WithdrawalCapExceeded
+        );
+        let available = token_vault.available_funds(&ctx.accounts.vault_token_account);
+        require!(amount <= available, VaultError::InsufficientVaultBalance);
+
+        let seeds = &[VaultState::SEED, &[ctx.accounts.vault_state.bump]];
+        let signer_seeds = &[&seeds[..]];
+        let cpi_accounts = token::Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.vault_state.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        token_vault.pending_withdrawal = None;
+        token_vault.epoch_withdrawn = epoch_withdrawn;
+        token_vault.total_withdrawn = token_vault
+            .total_withdrawn
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+
+        emit!(EmergencyWithdrawal {
+            vault: ctx.accounts.vault_state.key(),
+            authority: ctx.accounts.authority.key(),
+            amount,
+            mint: Some(token_vault.mint),
+        });
+        Ok(())
+    }
+}
+
+fn apply_approvers(
+    vault: &mut Account<VaultState>,
+    approvers: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<()> {
+    validate_approver_set(&approvers, threshold)?;
+    vault.approvers = approvers;
+    vault.approval_threshold = threshold;
+    emit!(ApproversUpdated {
+        vault: vault.key(),
+        approvers: vault.approvers.clone(),
+        threshold,
+    });
+    Ok(())
+}
+
+fn validate_approver_set(approvers: &[Pubkey], threshold: u8) -> Result<()> {
+    require!(approvers.len() <= MAX_APPROVERS, VaultError::InvalidApproverSet);
+    require!(threshold as usize <= approvers.len(), VaultError::InvalidApproverSet);
+    require!(approvers.is_empty() == (threshold == 0), VaultError::InvalidApproverSet);
+    for (index, approver) in approvers.iter().enumerate() {
+        require!(!approvers[..index].contains(approver), VaultError::InvalidApproverSet);
+    }
+    Ok(())
+}
+
+/// Escrow accounts and stakers needed to hand stakes back when a wager is unwound.
+struct EscrowRefund<'info> {
+    escrow: Option<AccountInfo<'info>>,
+    system_program: Option<AccountInfo<'info>>,
+    primary_staker: Option<AccountInfo<'info>>,
+    secondary_staker: Option<AccountInfo<'info>>,
+}
+
+/// Releases a wager without a result: house reservations are dropped from the relevant
+/// `total_locked` and escrowed stakes are returned. Returns the `(primary, secondary)` refunds.
+fn unwind_wager<'info>(
+    wager: &Account<'info, WagerState>,
+    vault: &mut VaultState,
+    token_vault: Option<&mut Account<'info, TokenVaultState>>,
+    refund: EscrowRefund<'info>,
+) -> Result<(u64, u64)> {
+    match wager.mode {
+        WagerMode::House => {
+            match wager.mint {
+                Some(mint) => {
+                    let token_vault = token_vault.ok_or(VaultError::WagerDenominationMismatch)?;
+                    require_keys_eq!(token_vault.mint, mint, VaultError::WagerDenominationMismatch);
+                    token_vault.total_locked = token_vault
+                        .total_locked
+                        .checked_sub(wager.locked_amount)
+                        .ok_or(VaultError::Overflow)?;
+                }
+                None => {
+                    vault.total_locked = vault
+                        .total_locked
+                        .checked_sub(wager.locked_amount)
+                        .ok_or(VaultError::Overflow)?;
+                }
+            }
+            Ok((0, 0))
+        }
+        WagerMode::Escrow => {
+            let (escrow, system_program) = match (refund.escrow, refund.system_program) {
+                (Some(escrow), Some(system_program)) => (escrow, system_program),
+                _ => return Err(VaultError::EscrowAccountMissing.into()),
+            };
+            let wager_key = wager.key();
+            let escrow_bump = [wager.escrow_bump];
+            let escrow_seeds: &[&[u8]] =
+                &[WagerState::ESCROW_SEED, wager_key.as_ref(), &escrow_bump];
+            let signer_seeds = &[escrow_seeds];
+            let refunds = [
+                (Some(wager.recipient_primary), refund.primary_staker, wager.primary_stake),
+                (wager.recipient_secondary, refund.secondary_staker, wager.secondary_stake),
+            ];
+            for (expected, staker, stake) in refunds {
+                if stake == 0 {
+                    continue;
+                }
+                let staker = staker.ok_or(VaultError::InvalidRecipient)?;
+                require!(Some(staker.key()) == expected, VaultError::InvalidRecipient);
+                let cpi_accounts = system_program::Transfer {
+                    from: escrow.clone(),
+                    to: staker,
+                };
+                let cpi_ctx = CpiContext::new_with_signer(
+                    system_program.clone(),
+                    cpi_accounts,
+                    signer_seeds,
+                );
+                system_program::transfer(cpi_ctx, stake)?;
+            }
+            Ok((wager.primary_stake, wager.secondary_stake))
+        }
+    }
+}
+
+/// Moves lamports out of the program-owned vault account by adjusting both balances; the
+/// system program only debits accounts it owns.
+fn debit_vault(vault: &AccountInfo, destination: &AccountInfo, amount: u64) -> Result<()> {
+    let vault_balance = vault
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(VaultError::InsufficientVaultBalance)?;
+    let destination_balance = destination
+        .lamports()
+        .checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+    **vault.try_borrow_mut_lamports()? = vault_balance;
+    **destination.try_borrow_mut_lamports()? = destination_balance;
+    Ok(())
+}
+
+/// Splits `fee` across the two payouts in proportion to their size; the primary share
+/// absorbs rounding. Returns the net `(primary, secondary)` amounts.
+pub fn split_fee(primary_amount: u64, secondary_amount: u64, fee: u64) -> (u64, u64) {
+    let total = primary_amount as u128 + secondary_amount as u128;
+    if fee == 0 || total == 0 {
+        return (primary_amount, secondary_amount);
+    }
+    let secondary_fee = (fee as u128 * secondary_amount as u128 / total) as u64;
+    let primary_fee = fee - secondary_fee;
+    (primary_amount - primary_fee, secondary_amount - secondary_fee)
+}
+
+/// Checks that `proposal` authorises `action` and marks it executed.
+/// A no-op while the vault has no multisig threshold configured.
+fn consume_proposal(
+    vault: &VaultState,
+    proposal: Option<&mut Account<ProposalState>>,
+    action: &ProposalAction,
+) -> Result<()> {
+    if !vault.multisig_enabled() {
+        return Ok(());
+    }
+    let proposal = proposal.ok_or(VaultError::ProposalRequired)?;
+    require!(!proposal.executed, VaultError::ProposalAlreadyExecuted);
+    require!(proposal.action == *action, VaultError::ProposalActionMismatch);
+    let approvals = vault.approval_count(proposal);
+    require!(
+        approvals >= vault.approval_threshold,
+        VaultError::ProposalThresholdNotMet
+    );
+    proposal.executed = true;
+    emit!(ProposalExecuted {
+        vault: proposal.vault,
+        proposal: proposal.key(),
+        approvals,
+    });
+    Ok(())
+}
+
+/// House reservations spend treasury funds, so they need the same approval as other treasury
+/// movements: an approved proposal under multisig, otherwise the vault authority's signature,
+/// either directly or as co-signer on a battle authority CPI.
+fn authorize_house_lock(
+    vault: &VaultState,
+    signers: [Option<Pubkey>; 2],
+    proposal: Option<&mut Account<ProposalState>>,
+    action: &ProposalAction,
+) -> Result<()> {
+    if vault.multisig_enabled() {
+        return consume_proposal(vault, proposal, action);
+    }
+    require!(signers.contains(&Some(vault.authority)), VaultError::HouseLockNotApproved);
+    Ok(())
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct InitializeVaultParams {
+    pub matka_token_mint: Option<Pubkey>,
+    /// Program-derived signer (battle_core platform PDA) allowed to lock and settle via CPI.
+    pub battle_authority: Option<Pubkey>,
+    /// Compliance key allowed to pause and unpause the vault alongside the authority.
+    pub pauser: Option<Pubkey>,
+    /// battle_core program that owns the battle accounts wagers are locked against.
+    pub battle_program: Option<Pubkey>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LockWagerParams {
+    /// House reservation, or the primary staker's deposit in escrow mode.
+    pub amount: u64,
+    pub primary_recipient: Pubkey,
+    pub secondary_recipient: Option<Pubkey>,
+    pub mode: WagerMode,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SettlePayoutParams {
+    pub primary_amount: u64,
+    pub secondary_amount: Option<u64>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [VaultState::SEED],
+        bump,
+        space = VaultState::space()
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositTreasury<'info> {
+    #[account(mut)]
+    pub treasury_funder: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: LockWagerParams)]
+pub struct LockWager<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.is_operator(&authority.key()) @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            WagerState::SEED,
+            vault_state.key().as_ref(),
+            battle.key().as_ref()
+        ],
+        bump,
+        space = WagerState::space()
+    )]
+    pub wager_state: Account<'info, WagerState>,
+    /// CHECK: Deserialized and verified as a battle_core `BattleState` in the handler.
+    pub battle: UncheckedAccount<'info>,
+    /// Escrow holding player stakes; required in escrow mode.
+    #[account(
+        mut,
+        seeds = [WagerState::ESCROW_SEED, wager_state.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Primary player's `PlayerWagerLimits` PDA; may be uninitialised.
+    #[account(
+        mut,
+        seeds = [
+            PlayerWagerLimits::SEED,
+            vault_state.key().as_ref(),
+            params.primary_recipient.as_ref()
+        ],
+        bump
+    )]
+    pub primary_limits: UncheckedAccount<'info>,
+    /// CHECK: Player's battle_core `PlayerAttestation`; verified in the handler when the vault
+    /// has an attester.
+    pub primary_attestation: Option<UncheckedAccount<'info>>,
+    /// CHECK: Primary player's `DenyListEntry` PDA; exists only if the wallet is denied.
+    #[account(
+        seeds = [
+            DenyListEntry::SEED,
+            vault_state.key().as_ref(),
+            params.primary_recipient.as_ref()
+        ],
+        bump
+    )]
+    pub primary_deny_entry: UncheckedAccount<'info>,
+    /// CHECK: Primary player's battle_core `PlayerLimits` PDA; may be uninitialised.
+    #[account(
+        seeds = [
+            PlayerLimitsSnapshot::SEED,
+            vault_state.battle_authority.unwrap_or_default().as_ref(),
+            params.primary_recipient.as_ref()
+        ],
+        bump,
+        seeds::program = vault_state.battle_program.unwrap_or_default()
+    )]
+    pub primary_self_exclusion: UncheckedAccount<'info>,
+    /// Vault authority co-signing a house-mode lock requested by the battle authority.
+    pub house_authority: Option<Signer<'info>>,
+    /// Approved `LockHouseWager` proposal; replaces the co-signature once multisig is enabled.
+    #[account(
+        mut,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &proposal.proposal_id.to_le_bytes()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, ProposalState>>,
+}
+
+#[derive(Accounts)]
+pub struct SettlePayout<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.is_operator(&authority.key()) @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [
+            WagerState::SEED,
+            vault_state.key().as_ref(),
+            wager_state.battle.as_ref()
+        ],
+        bump = wager_state.bump,
+        constraint = wager_state.mint.is_none() @ VaultError::WagerDenominationMismatch,
+        close = rent_recipient
+    )]
+    pub wager_state: Account<'info, WagerState>,
+    /// CHECK: Receives the wager account rent; must match the original payer.
+    #[account(mut, address = wager_state.rent_payer @ VaultError::InvalidRecipient)]
+    pub rent_recipient: UncheckedAccount<'info>,
+    /// CHECK: Verified as the wager's battle_core `BattleState` in the handler.
+    #[account(address = wager_state.battle @ VaultError::InvalidBattleAccount)]
+    pub battle: UncheckedAccount<'info>,
+    /// CHECK: Recipient validation occurs against stored keys before transfers.
+    #[account(mut)]
+    pub primary_recipient: UncheckedAccount<'info>,
+    /// CHECK: Secondary recipient is optional; validated if provided.
+    #[account(mut)]
+    pub secondary_recipient: Option<UncheckedAccount<'info>>,
+    /// Escrow holding player stakes; required in escrow mode.
+    #[account(
+        mut,
+        seeds = [WagerState::ESCROW_SEED, wager_state.key().as_ref()],
+        bump = wager_state.escrow_bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Must match the fee recipient in the vault's fee config; required when a fee applies.
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+    /// Approved proposal; required for authority-signed settlements once multisig is enabled.
+    #[account(
+        mut,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &proposal.proposal_id.to_le_bytes()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, ProposalState>>,
+    /// CHECK: Primary player's `PlayerWagerLimits` PDA; required for escrow wagers.
+    #[account(
+        mut,
+        seeds = [
+            PlayerWagerLimits::SEED,
+            vault_state.key().as_ref(),
+            wager_state.recipient_primary.as_ref()
+        ],
+        bump
+    )]
+    pub primary_limits: Option<UncheckedAccount<'info>>,
+    /// CHECK: Secondary player's `PlayerWagerLimits` PDA; required for escrow wagers with a
+    /// secondary stake.
+    #[account(
+        mut,
+        seeds = [
+            PlayerWagerLimits::SEED,
+            vault_state.key().as_ref(),
+            wager_state.recipient_secondary.unwrap_or_default().as_ref()
+        ],
+        bump
+    )]
+    pub secondary_limits: Option<UncheckedAccount<'info>>,
+    /// CHECK: Primary recipient's `DenyListEntry` PDA; exists only if the wallet is denied.
+    #[account(
+        mut,
+        seeds = [
+            DenyListEntry::SEED,
+            vault_state.key().as_ref(),
+            wager_state.recipient_primary.as_ref()
+        ],
+        bump
+    )]
+    pub primary_deny_entry: UncheckedAccount<'info>,
+    /// CHECK: Secondary recipient's `DenyListEntry` PDA; required when the wager has one.
+    #[account(
+        mut,
+        seeds = [
+            DenyListEntry::SEED,
+            vault_state.key().as_ref(),
+            wager_state.recipient_secondary.unwrap_or_default().as_ref()
+        ],
+        bump
+    )]
+    pub secondary_deny_entry: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTokenVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        has_one = authority @ VaultError::Unauthorized,
+        constraint = vault_state.matka_token_mint == Some(mint.key()) @ VaultError::UnsupportedMint
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [TokenVaultState::SEED, vault_state.key().as_ref(), mint.key().as_ref()],
+        bump,
+        space = TokenVaultState::space()
+    )]
+    pub token_vault: Account<'info, TokenVaultState>,
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = vault_state
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositTreasuryToken<'info> {
+    pub treasury_funder: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [TokenVaultState::SEED, token_vault.vault.as_ref(), token_vault.mint.as_ref()],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVaultState>,
+    #[account(
+        mut,
+        token::mint = token_vault.mint,
+        token::authority = treasury_funder
+    )]
+    pub funder_token_account: Account<'info, TokenAccount>,
+    #[account(mut, address = token_vault.token_account)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: LockWagerParams)]
+pub struct LockWagerToken<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.is_operator(&authority.key()) @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [TokenVaultState::SEED, vault_state.key().as_ref(), token_vault.mint.as_ref()],
+        bump = token_vault.bump,
+        constraint = vault_state.matka_token_mint == Some(token_vault.mint)
+            @ VaultError::UnsupportedMint
+    )]
+    pub token_vault: Account<'info, TokenVaultState>,
+    #[account(address = token_vault.token_account)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            WagerState::SEED,
+            vault_state.key().as_ref(),
+            battle.key().as_ref()
+        ],
+        bump,
+        space = WagerState::space()
+    )]
+    pub wager_state: Account<'info, WagerState>,
+    /// CHECK: Deserialized and verified as a battle_core `BattleState` in the handler.
+    pub battle: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Primary player's `PlayerWagerLimits` PDA; may be uninitialised.
+    #[account(
+        mut,
+        seeds = [
+            PlayerWagerLimits::SEED,
+            vault_state.key().as_ref(),
+            params.primary_recipient.as_ref()
+        ],
+        bump
+    )]
+    pub primary_limits: UncheckedAccount<'info>,
+    /// CHECK: Player's battle_core `PlayerAttestation`; verified in the handler when the vault
+    /// has an attester.
+    pub primary_attestation: Option<UncheckedAccount<'info>>,
+    /// CHECK: Primary player's `DenyListEntry` PDA; exists only if the wallet is denied.
+    #[account(
+        seeds = [
+            DenyListEntry::SEED,
+            vault_state.key().as_ref(),
+            params.primary_recipient.as_ref()
+        ],
+        bump
+    )]
+    pub primary_deny_entry: UncheckedAccount<'info>,
+    /// CHECK: Primary player's battle_core `PlayerLimits` PDA; may be uninitialised.
+    #[account(
+        seeds = [
+            PlayerLimitsSnapshot::SEED,
+            vault_state.battle_authority.unwrap_or_default().as_ref(),
+            params.primary_recipient.as_ref()
+        ],
+        bump,
+        seeds::program = vault_state.battle_program.unwrap_or_default()
+    )]
+    pub primary_self_exclusion: UncheckedAccount<'info>,
+    /// Vault authority co-signing a house-mode lock requested by the battle authority.
+    pub house_authority: Option<Signer<'info>>,
+    /// Approved `LockHouseWager` proposal; replaces the co-signature once multisig is enabled.
+    #[account(
+        mut,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &proposal.proposal_id.to_le_bytes()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, ProposalState>>,
+}
+
+#[derive(Accounts)]
+pub struct SettlePayoutToken<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.is_operator(&authority.key()) @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [TokenVaultState::SEED, vault_state.key().as_ref(), token_vault.mint.as_ref()],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVaultState>,
+    #[account(mut, address = token_vault.token_account)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [
+            WagerState::SEED,
+            vault_state.key().as_ref(),
+            wager_state.battle.as_ref()
+        ],
+        bump = wager_state.bump,
+        constraint = wager_state.mint == Some(token_vault.mint)
+            @ VaultError::WagerDenominationMismatch,
+        close = rent_recipient
+    )]
+    pub wager_state: Account<'info, WagerState>,
+    /// CHECK: Receives the wager account rent; must match the original payer.
+    #[account(mut, address = wager_state.rent_payer @ VaultError::InvalidRecipient)]
+    pub rent_recipient: UncheckedAccount<'info>,
+    /// CHECK: Verified as the wager's battle_core `BattleState` in the handler.
+    #[account(address = wager_state.battle @ VaultError::InvalidBattleAccount)]
+    pub battle: UncheckedAccount<'info>,
+    #[account(mut, token::mint = token_vault.mint)]
+    pub primary_recipient_token: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = token_vault.mint)]
+    pub secondary_recipient_token: Option<Account<'info, TokenAccount>>,
+    /// Token account owned by the configured fee recipient; required when a fee applies.
+    #[account(mut, token::mint = token_vault.mint)]
+    pub fee_recipient_token: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    /// Approved proposal; required for authority-signed settlements once multisig is enabled.
+    #[account(
+        mut,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &proposal.proposal_id.to_le_bytes()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, ProposalState>>,
+    /// CHECK: Primary recipient's `DenyListEntry` PDA; exists only if the wallet is denied.
+    #[account(
+        seeds = [
+            DenyListEntry::SEED,
+            vault_state.key().as_ref(),
+            wager_state.recipient_primary.as_ref()
+        ],
+        bump
+    )]
+    pub primary_deny_entry: UncheckedAccount<'info>,
+    /// CHECK: Secondary recipient's `DenyListEntry` PDA; required when the wager has one.
+    #[account(
+        seeds = [
+            DenyListEntry::SEED,
+            vault_state.key().as_ref(),
+            wager_state.recipient_secondary.unwrap_or_default().as_ref()
+        ],
+        bump
+    )]
+    pub secondary_deny_entry: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct AssignSecondaryRecipient<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.is_operator(&authority.key()) @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [
+            WagerState::SEED,
+            vault_state.key().as_ref(),
+            wager_state.battle.as_ref()
+        ],
+        bump = wager_state.bump
+    )]
+    pub wager_state: Account<'info, WagerState>,
+    /// CHECK: Verified as the wager's battle_core `BattleState` in the handler.
+    #[account(address = wager_state.battle @ VaultError::InvalidBattleAccount)]
+    pub battle: UncheckedAccount<'info>,
+    /// Secondary staker; must sign and match `recipient` in escrow mode.
+    #[account(mut)]
+    pub staker: Option<Signer<'info>>,
+    #[account(
+        mut,
+        seeds = [WagerState::ESCROW_SEED, wager_state.key().as_ref()],
+        bump = wager_state.escrow_bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+    /// CHECK: Secondary player's `PlayerWagerLimits` PDA; may be uninitialised.
+    #[account(
+        mut,
+        seeds = [PlayerWagerLimits::SEED, vault_state.key().as_ref(), recipient.as_ref()],
+        bump
+    )]
+    pub secondary_limits: UncheckedAccount<'info>,
+    /// CHECK: Player's battle_core `PlayerAttestation`; verified in the handler when the vault
+    /// has an attester.
+    pub secondary_attestation: Option<UncheckedAccount<'info>>,
+    /// CHECK: Secondary player's `DenyListEntry` PDA; exists only if the wallet is denied.
+    #[account(
+        seeds = [DenyListEntry::SEED, vault_state.key().as_ref(), recipient.as_ref()],
+        bump
+    )]
+    pub secondary_deny_entry: UncheckedAccount<'info>,
+    /// CHECK: Secondary player's battle_core `PlayerLimits` PDA; may be uninitialised.
+    #[account(
+        seeds = [
+            PlayerLimitsSnapshot::SEED,
+            vault_state.battle_authority.unwrap_or_default().as_ref(),
+            recipient.as_ref()
+        ],
+        bump,
+        seeds::program = vault_state.battle_program.unwrap_or_default()
+    )]
+    pub secondary_self_exclusion: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VoidWager<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.is_operator(&authority.key()) @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [
+            WagerState::SEED,
+            vault_state.key().as_ref(),
+            wager_state.battle.as_ref()
+        ],
+        bump = wager_state.bump,
+        close = rent_recipient
+    )]
+    pub wager_state: Account<'info, WagerState>,
+    /// CHECK: Receives the wager account rent; must match the original payer.
+    #[account(mut, address = wager_state.rent_payer @ VaultError::InvalidRecipient)]
+    pub rent_recipient: UncheckedAccount<'info>,
+    /// CHECK: Verified as the wager's battle_core `BattleState` in the handler.
+    #[account(address = wager_state.battle @ VaultError::InvalidBattleAccount)]
+    pub battle: UncheckedAccount<'info>,
+    /// CHECK: Must match the stored primary recipient; refunded in escrow mode.
+    #[account(mut)]
+    pub primary_staker: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match the stored secondary recipient; refunded in escrow mode.
+    #[account(mut)]
+    pub secondary_staker: Option<UncheckedAccount<'info>>,
+    #[account(
+        mut,
+        seeds = [WagerState::ESCROW_SEED, wager_state.key().as_ref()],
+        bump = wager_state.escrow_bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    /// Per-mint totals; required to release a token wager's reservation.
+    #[account(
+        mut,
+        seeds = [TokenVaultState::SEED, vault_state.key().as_ref(), token_vault.mint.as_ref()],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Option<Account<'info, TokenVaultState>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+impl<'info> VoidWager<'info> {
+    fn escrow_refund(&self) -> EscrowRefund<'info> {
+        EscrowRefund {
+            escrow: self.escrow.as_ref().map(|account| account.to_account_info()),
+            system_program: self.system_program.as_ref().map(|program| program.to_account_info()),
+            primary_staker: self.primary_staker.as_ref().map(|account| account.to_account_info()),
+            secondary_staker: self
+                .secondary_staker
+                .as_ref()
+                .map(|account| account.to_account_info()),
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExpireWager<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [
+            WagerState::SEED,
+            vault_state.key().as_ref(),
+            wager_state.battle.as_ref()
+        ],
+        bump = wager_state.bump,
+        close = rent_recipient
+    )]
+    pub wager_state: Account<'info, WagerState>,
+    /// CHECK: Receives the wager account rent less the crank reward; must match the payer.
+    #[account(mut, address = wager_state.rent_payer @ VaultError::InvalidRecipient)]
+    pub rent_recipient: UncheckedAccount<'info>,
+    /// CHECK: The wager's battle_core `BattleState`, or its closed address; checked in
+    /// the handler.
+    #[account(address = wager_state.battle @ VaultError::InvalidBattleAccount)]
+    pub battle: UncheckedAccount<'info>,
+    /// CHECK: Must match the stored primary recipient; refunded in escrow mode.
+    #[account(mut)]
+    pub primary_staker: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match the stored secondary recipient; refunded in escrow mode.
+    #[account(mut)]
+    pub secondary_staker: Option<UncheckedAccount<'info>>,
+    #[account(
+        mut,
+        seeds = [WagerState::ESCROW_SEED, wager_state.key().as_ref()],
+        bump = wager_state.escrow_bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    /// Per-mint totals; required to release a token wager's reservation.
+    #[account(
+        mut,
+        seeds = [TokenVaultState::SEED, vault_state.key().as_ref(), token_vault.mint.as_ref()],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Option<Account<'info, TokenVaultState>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+impl<'info> ExpireWager<'info> {
+    fn escrow_refund(&self) -> EscrowRefund<'info> {
+        EscrowRefund {
+            escrow: self.escrow.as_ref().map(|account| account.to_account_info()),
+            system_program: self.system_program.as_ref().map(|program| program.to_account_info()),
+            primary_staker: self.primary_staker.as_ref().map(|account| account.to_account_info()),
+            secondary_staker: self
+                .secondary_staker
+                .as_ref()
+                .map(|account| account.to_account_info()),
+        }
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddDenyListEntry<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        has_one = authority @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [DenyListEntry::SEED, vault_state.key().as_ref(), wallet.as_ref()],
+        bump,
+        space = DenyListEntry::space()
+    )]
+    pub deny_entry: Account<'info, DenyListEntry>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveDenyListEntry<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        has_one = authority @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [DenyListEntry::SEED, vault_state.key().as_ref(), deny_entry.wallet.as_ref()],
+        bump = deny_entry.bump,
+        close = authority
+    )]
+    pub deny_entry: Account<'info, DenyListEntry>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveFrozenFunds<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        has_one = authority @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [DenyListEntry::SEED, vault_state.key().as_ref(), deny_entry.wallet.as_ref()],
+        bump = deny_entry.bump
+    )]
+    pub deny_entry: Account<'info, DenyListEntry>,
+    /// CHECK: The denied wallet on release, or the treasury destination on forfeit.
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    /// Approved proposal; required once multisig is enabled.
+    #[account(
+        mut,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &proposal.proposal_id.to_le_bytes()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, ProposalState>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeWagerLimits<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+    #[account(
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        init,
+        payer = player,
+        seeds = [PlayerWagerLimits::SEED, vault_state.key().as_ref(), player.key().as_ref()],
+        bump,
+        space = PlayerWagerLimits::space()
+    )]
+    pub player_limits: Account<'info, PlayerWagerLimits>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetWagerLimits<'info> {
+    pub player: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PlayerWagerLimits::SEED, player_limits.vault.as_ref(), player.key().as_ref()],
+        bump = player_limits.bump,
+        has_one = player @ VaultError::Unauthorized
+    )]
+    pub player_limits: Account<'info, PlayerWagerLimits>,
+}
+
+#[derive(Accounts)]
+pub struct Reconcile<'info> {
+    pub caller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump
+    )]
+    pub vault_state: Account<'info, VaultState>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVaultConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        has_one = authority @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+}
+
+/// Treasury-affecting config changes; once multisig is enabled they also need an approved
+/// proposal for the exact change.
+#[derive(Accounts)]
+pub struct UpdateGovernedConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        has_one = authority @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    /// Approved proposal; required once multisig is enabled.
+    #[account(
+        mut,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &proposal.proposal_id.to_le_bytes()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, ProposalState>>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptVaultAuthority<'info> {
+    pub new_authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.pending_authority == Some(new_authority.key())
+            @ VaultError::InvalidPendingAuthority
+    )]
+    pub vault_state: Account<'info, VaultState>,
+}
+
+#[derive(Accounts)]
+pub struct SetVaultPause<'info> {
+    pub pauser: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.can_pause(&pauser.key()) @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.authority == authority.key() @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    /// CHECK: Must be the treasury destination registered in the withdrawal policy.
+    #[account(
+        mut,
+        constraint = vault_state.withdrawal_policy.treasury_destination == Some(destination.key())
+            @ VaultError::InvalidTreasuryDestination
+    )]
+    pub destination: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    /// Approved proposal; required once multisig is enabled.
+    #[account(
+        mut,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &proposal.proposal_id.to_le_bytes()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, ProposalState>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTokenVaultConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        has_one = authority @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [TokenVaultState::SEED, vault_state.key().as_ref(), token_vault.mint.as_ref()],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVaultState>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTokenVaultGovernedConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        has_one = authority @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [TokenVaultState::SEED, vault_state.key().as_ref(), token_vault.mint.as_ref()],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVaultState>,
+    /// Approved proposal; required once multisig is enabled.
+    #[account(
+        mut,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &proposal.proposal_id.to_le_bytes()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, ProposalState>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTokenWithdraw<'info> {
+    pub pauser: Signer<'info>,
+    #[account(
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.can_pause(&pauser.key()) @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [TokenVaultState::SEED, vault_state.key().as_ref(), token_vault.mint.as_ref()],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVaultState>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasuryToken<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.authority == authority.key() @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [TokenVaultState::SEED, vault_state.key().as_ref(), token_vault.mint.as_ref()],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVaultState>,
+    #[account(mut, address = token_vault.token_account)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    /// Must be owned by the treasury destination registered in the withdrawal policy.
+    #[account(
+        mut,
+        token::mint = token_vault.mint,
+        constraint = vault_state.withdrawal_policy.treasury_destination == Some(destination.owner)
+            @ VaultError::InvalidTreasuryDestination
+    )]
+    pub destination: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    /// Approved proposal; required once multisig is enabled.
+    #[account(
+        mut,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &proposal.proposal_id.to_le_bytes()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, ProposalState>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.approvers.contains(&proposer.key()) @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        init,
+        payer = proposer,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &vault_state.next_proposal_id.to_le_bytes()
+        ],
+        bump,
+        space = ProposalState::space()
+    )]
+    pub proposal: Account<'info, ProposalState>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    pub approver: Signer<'info>,
+    #[account(
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.approvers.contains(&approver.key()) @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &proposal.proposal_id.to_le_bytes()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, ProposalState>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteApproverChange<'info> {
+    pub approver: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VaultState::SEED],
+        bump = vault_state.bump,
+        constraint = vault_state.approvers.contains(&approver.key()) @ VaultError::Unauthorized
+    )]
+    pub vault_state: Account<'info, VaultState>,
+    #[account(
+        mut,
+        seeds = [
+            ProposalState::SEED,
+            vault_state.key().as_ref(),
+            &proposal.proposal_id.to_le_bytes()
+        ],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, ProposalState>,
+}
+
+#[account]
+pub struct VaultState {
+    pub authority: Pubkey,
+    pub bump: u8,
+    pub paused: bool,
+    pub total_deposited: u64,
+    pub total_locked: u64,
+    pub total_settled: u64,
+    pub matka_token_mint: Option<Pubkey>,
+    pub battle_authority: Option<Pubkey>,
+    pub battle_program: Option<Pubkey>,
+    pub pauser: Option<Pubkey>,
+    pub pause_reason: Option<u16>,
+    /// Proposed authority that must sign `accept_authority_transfer` to take over.
+    pub pending_authority: Option<Pubkey>,
+    pub withdrawal_policy: WithdrawalPolicy,
+    /// Looser policy queued behind the current withdrawal delay.
+    pub pending_withdrawal_policy: Option<WithdrawalPolicy>,
+    pub pending_policy_effective_at: i64,
+    pub pending_withdrawal: Option<PendingWithdrawal>,
+    /// Clock epoch that `epoch_withdrawn` applies to.
+    pub withdrawal_epoch: u64,
+    pub epoch_withdrawn: u64,
+    pub total_withdrawn: u64,
+    /// M-of-N approver set; an empty set with a zero threshold leaves multisig disabled.
+    pub approvers: Vec<Pubkey>,
+    pub approval_threshold: u8,
+    pub next_proposal_id: u64,
+    pub fee_config: FeeConfig,
+    /// Looser fee config queued behind the withdrawal delay.
+    pub pending_fee_config: Option<FeeConfig>,
+    pub pending_fee_effective_at: i64,
+    /// Cumulative lamports routed to the fee recipient.
+    pub total_fees: u64,
+    /// Lamports paid out of the vault account itself (house-mode payouts and their fees).
+    pub total_house_paid_out: u64,
+    pub expiry_policy: ExpiryPolicy,
+    /// battle_core attester whose attestations staking players must hold; `None` disables
+    /// the check.
+    pub attester: Option<Pubkey>,
+    /// Lamports held in the vault for denied recipients, pending review.
+    pub total_frozen: u64,
+    /// Frozen escrow-mode shares moved from the escrow into the vault account.
+    pub total_escrow_frozen: u64,
+    /// Frozen lamports later released to their wallet or forfeited to the treasury.
+    pub total_frozen_released: u64,
+}
+
+impl VaultState {
+    pub const SEED: &'static [u8] = b"vault-state";
+
+    pub fn space() -> usize {
+        8 + 32
+            + 1
+            + 1
+            + 8
+            + 8
+            + 8
+            + 1
+            + 32
+            + 1
+            + 32
+            + (1 + 32)
+            + 1
+            + 2
+            + 1
+            + 32
+            + WithdrawalPolicy::space()
+            + (1 + WithdrawalPolicy::space())
+            + 8
+            + 1
+            + PendingWithdrawal::space()
+            + 8
+            + 8
+            + 8
+            + 4
+            + 32 * MAX_APPROVERS
+            + 1
+            + 8
+            + FeeConfig::space()
+            + (1 + FeeConfig::space())
+            + 8
+            + 8
+            + (1 + 32)
+            + 8
+            + ExpiryPolicy::space()
+            + (1 + 32)
+            + 8
+            + 8
+            + 8
+    }
+
+    /// Multisig is on whenever approvers are configured; their threshold is always at least one.
+    pub fn multisig_enabled(&self) -> bool {
+        !self.approvers.is_empty()
+    }
+
+    /// Counts approvals from keys that are still in the current approver set.
+    pub fn approval_count(&self, proposal: &ProposalState) -> u8 {
+        proposal
+            .approvals
+            .iter()
+            .filter(|approver| self.approvers.contains(approver))
+            .count() as u8
+    }
+
+    pub fn is_operator(&self, key: &Pubkey) -> bool {
+        *key == self.authority || Some(*key) == self.battle_authority
+    }
+
+    /// Applies a queued fee config once its delay has elapsed.
+    pub fn refresh_fee_config(&mut self, now: i64) {
+        if let Some(pending) = self.pending_fee_config {
+            if now >= self.pending_fee_effective_at {
+                self.fee_config = pending;
+                self.pending_fee_config = None;
+                self.pending_fee_effective_at = 0;
+            }
+        }
+    }
+
+    /// Applies a queued withdrawal policy once its delay has elapsed.
+    pub fn refresh_withdrawal_policy(&mut self, now: i64) {
+        if let Some(pending) = self.pending_withdrawal_policy {
+            if now >= self.pending_policy_effective_at {
+                self.withdrawal_policy = pending;
+                self.pending_withdrawal_policy = None;
+                self.pending_policy_effective_at = 0;
+            }
+        }
+    }
+
+    /// The authority can always pause; the pauser can pause but holds no treasury rights.
+    pub fn can_pause(&self, key: &Pubkey) -> bool {
+        *key == self.authority || Some(*key) == self.pauser
+    }
+
+    pub fn available_funds(&self, vault_account: AccountInfo<'_>) -> Result<u64> {
+        let rent = Rent::get()?.minimum_balance(Self::space());
+        let balance = vault_account
+            .lamports()
+            .saturating_sub(rent);
+        Ok(balance
+            .saturating_sub(self.total_locked)
+            .saturating_sub(self.total_frozen))
+    }
+
+    /// Lamport balance implied by the counters: rent plus deposits, less everything
+    /// that has left the vault account. Escrow-mode stakes only reach the vault when a
+    /// denied recipient's share is frozen.
+    pub fn expected_balance(&self, rent: u64) -> u64 {
+        rent.saturating_add(self.total_deposited)
+            .saturating_add(self.total_escrow_frozen)
+            .saturating_sub(self.total_house_paid_out)
+            .saturating_sub(self.total_withdrawn)
+            .saturating_sub(self.total_frozen_released)
+    }
+}
+
+#[account]
+pub struct WagerState {
+    pub vault: Pubkey,
+    pub battle: Pubkey,
+    pub bump: u8,
+    pub locked_amount: u64,
+    pub recipient_primary: Pubkey,
+    pub recipient_secondary: Option<Pubkey>,
+    pub rent_payer: Pubkey,
+    pub locked_at: i64,
+    pub settled_at: Option<i64>,
+    pub settled: bool,
+    pub mode: WagerMode,
+    pub escrow_bump: u8,
+    pub primary_stake: u64,
+    pub secondary_stake: u64,
+    /// SPL mint for token wagers; `None` for lamport wagers.
+    pub mint: Option<Pubkey>,
+}
+
+impl WagerState {
+    pub const SEED: &'static [u8] = b"wager-state";
+    pub const ESCROW_SEED: &'static [u8] = b"wager-escrow";
+
+    pub fn space() -> usize {
+        8
+            + 32
+            + 32
+            + 1
+            + 8
+            + 32
+            + (1 + 32)
+            + 32
+            + 8
+            + (1 + 8)
+            + 1
+            + 1
+            + 1
+            + 8
+            + 8
+            + (1 + 32)
+    }
+}
+
+/// Marks `wallet` as blocked from staking and from receiving payouts from this vault.
+#[account]
+pub struct DenyListEntry {
+    pub vault: Pubkey,
+    pub wallet: Pubkey,
+    pub bump: u8,
+    pub reason_code: u16,
+    pub added_by: Pubkey,
+    pub added_at: i64,
+    /// Payouts withheld from the wallet since it was denied.
+    pub frozen_amount: u64,
+}
+
+impl DenyListEntry {
+    pub const SEED: &'static [u8] = b"deny-list";
+
+    pub fn space() -> usize {
+        8 + 32 + 32 + 1 + 2 + 32 + 8 + 8
+    }
+
+    /// `account` is the seed-checked entry PDA, which only exists for denied wallets.
+    pub fn ensure_not_denied(account: &AccountInfo) -> Result<()> {
+        require!(account.data_is_empty(), VaultError::WalletDenied);
+        Ok(())
+    }
+
+    /// Adds `amount` to the wallet's frozen balance if it is denied. Returns whether it was.
+    fn hold(account: &AccountInfo, amount: u64) -> Result<bool> {
+        if account.data_is_empty() {
+            return Ok(false);
+        }
+        require_keys_eq!(*account.owner, crate::ID, VaultError::WalletDenied);
+        let mut data = account.try_borrow_mut_data()?;
+        let mut entry = Self::try_deserialize(&mut &data[..])?;
+        entry.frozen_amount = entry
+            .frozen_amount
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        entry.try_serialize(&mut &mut data[..])?;
+        Ok(true)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrozenFundsResolution {
+    /// Pay the frozen balance to the wallet after a cleared review.
+    Release,
+    /// Send the frozen balance to the treasury destination.
+    Forfeit,
+}
+
+/// A player's stake and net-loss limits plus the usage counted against them. Amounts are
+/// in lamports; token wagers are house-backed and only count towards the eligibility check.
+#[account]
+pub struct PlayerWagerLimits {
+    pub vault: Pubkey,
+    pub player: Pubkey,
+    pub bump: u8,
+    pub limits: LimitSchedule,
+    /// Looser limits requested by the player; they replace `limits` at `pending_effective_at`.
+    pub pending_limits: Option<LimitSchedule>,
+    pub pending_effective_at: i64,
+    /// Daily, weekly and monthly usage, matching [`LIMIT_PERIOD_SECS`].
+    pub usage: [PeriodUsage; 3],
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl PlayerWagerLimits {
+    pub const SEED: &'static [u8] = b"player-wager-limits";
+
+    pub fn space() -> usize {
+        8 + 32
+            + 32
+            + 1
+            + LimitSchedule::space()
+            + (1 + LimitSchedule::space())
+            + 8
+            + PeriodUsage::space() * LIMIT_PERIOD_SECS.len()
+            + 8
+            + 8
+    }
+
+    /// Applies a pending increase once its cooling delay is over and starts a fresh window
+    /// for every period that has elapsed.
+    pub fn refresh(&mut self, now: i64) {
+        if let Some(pending) = self.pending_limits {
+            if now >= self.pending_effective_at {
+                self.limits = pending;
+                self.pending_limits = None;
+                self.pending_effective_at = 0;
+            }
+        }
+        for (usage, period) in self.usage.iter_mut().zip(LIMIT_PERIOD_SECS) {
+            if now.saturating_sub(usage.window_start) >= period {
+                *usage = PeriodUsage {
+                    window_start: now,
+                    staked: 0,
+                    net_loss: 0,
+                };
+            }
+        }
+    }
+
+    /// Applies whatever part of `limits` tightens the current ones and queues the rest behind
+    /// [`LIMIT_INCREASE_COOLING_SECS`]; a request that loosens nothing drops any queued one.
+    pub fn request_limits(&mut self, limits: LimitSchedule, now: i64) -> Result<()> {
+        self.refresh(now);
+        let current = self.limits;
+        self.limits = current.tightest(&limits);
+        if limits.loosens(&current) {
+            self.pending_limits = Some(limits);
+            self.pending_effective_at =
+                now.checked_add(LIMIT_INCREASE_COOLING_SECS).ok_or(VaultError::Overflow)?;
+        } else {
+            self.pending_limits = None;
+            self.pending_effective_at = 0;
+        }
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Counts a new stake, rejecting it if it would break a stake limit or if losing it
+    /// could break a loss limit. A zero stake still fails once a loss limit is exceeded.
+    pub fn record_stake(&mut self, stake: u64) -> Result<()> {
+        for (usage, limit) in self.usage.iter_mut().zip(self.limits.periods()) {
+            let staked = usage.staked.checked_add(stake).ok_or(VaultError::Overflow)?;
+            if let Some(max_stake) = limit.max_stake {
+                require!(staked <= max_stake, VaultError::StakeLimitExceeded);
+            }
+            if let Some(max_net_loss) = limit.max_net_loss {
+                let worst_case = usage.net_loss as i128 + stake as i128;
+                require!(worst_case <= max_net_loss as i128, VaultError::LossLimitExceeded);
+            }
+            usage.staked = staked;
+        }
+        Ok(())
+    }
+
+    /// Adds the loss (or gain) from a settled stake to every window.
+    pub fn record_result(&mut self, stake: u64, returned: u64) -> Result<()> {
+        let loss = i64::try_from(stake as i128 - returned as i128)
+            .map_err(|_| VaultError::Overflow)?;
+        for usage in self.usage.iter_mut() {
+            usage.net_loss = usage.net_loss.checked_add(loss).ok_or(VaultError::Overflow)?;
+        }
+        Ok(())
+    }
+
+    /// Runs `apply` against the limits stored in the seed-checked PDA `account`. Players who
+    /// never created one have nothing to enforce.
+    fn update<F>(account: &AccountInfo, now: i64, apply: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        if account.data_is_empty() {
+            return Ok(());
+        }
+        require_keys_eq!(*account.owner, crate::ID, VaultError::InvalidPlayerLimits);
+        let mut data = account.try_borrow_mut_data()?;
+        let mut limits = Self::try_deserialize(&mut &data[..])?;
+        limits.refresh(now);
+        apply(&mut limits)?;
+        limits.try_serialize(&mut &mut data[..])
+    }
+}
+
+/// Caps for one limit period; `None` leaves that dimension unlimited.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PeriodLimit {
+    pub max_stake: Option<u64>,
+    pub max_net_loss: Option<u64>,
+}
+
+impl PeriodLimit {
+    pub const fn space() -> usize {
+        (1 + 8) + (1 + 8)
+    }
+
+    fn tightest(&self, other: &Self) -> Self {
+        Self {
+            max_stake: tightest_cap(self.max_stake, other.max_stake),
+            max_net_loss: tightest_cap(self.max_net_loss, other.max_net_loss),
+        }
+    }
+
+    fn loosens(&self, current: &Self) -> bool {
+        loosens_cap(self.max_stake, current.max_stake)
+            || loosens_cap(self.max_net_loss, current.max_net_loss)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LimitSchedule {
+    pub daily: PeriodLimit,
+    pub weekly: PeriodLimit,
+    pub monthly: PeriodLimit,
+}
+
+impl LimitSchedule {
+    pub const fn space() -> usize {
+        PeriodLimit::space() * 3
+    }
+
+    /// Limits in the same order as [`LIMIT_PERIOD_SECS`].
+    pub fn periods(&self) -> [PeriodLimit; 3] {
+        [self.daily, self.weekly, self.monthly]
+    }
+
+    /// The stricter of the two schedules, cap by cap.
+    pub fn tightest(&self, other: &Self) -> Self {
+        Self {
+            daily: self.daily.tightest(&other.daily),
+            weekly: self.weekly.tightest(&other.weekly),
+            monthly: self.monthly.tightest(&other.monthly),
+        }
+    }
+
+    /// Whether any cap in `self` allows more than the same cap in `current`.
+    pub fn loosens(&self, current: &Self) -> bool {
+        self.periods()
+            .iter()
+            .zip(current.periods())
+            .any(|(requested, current)| requested.loosens(&current))
+    }
+}
+
+fn tightest_cap(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (cap, None) | (None, cap) => cap,
+    }
+}
+
+fn loosens_cap(requested: Option<u64>, current: Option<u64>) -> bool {
+    match (requested, current) {
+        (_, None) => false,
+        (None, Some(_)) => true,
+        (Some(requested), Some(current)) => requested > current,
+    }
+}
+
+/// Stake and net loss accrued in the current window of one limit period.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PeriodUsage {
+    pub window_start: i64,
+    pub staked: u64,
+    /// Lamports lost minus lamports won; negative while the player is ahead.
+    pub net_loss: i64,
+}
+
+impl PeriodUsage {
+    pub const fn space() -> usize {
+        8 + 8 + 8
+    }
+}
+
+/// Per-mint custody and totals for SPL token wagers.
+#[account]
+pub struct TokenVaultState {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    /// Vault-owned associated token account for `mint`.
+    pub token_account: Pubkey,
+    pub bump: u8,
+    pub total_deposited: u64,
+    pub total_locked: u64,
+    pub total_settled: u64,
+    pub total_fees: u64,
+    /// Maximum token units withdrawn per cluster epoch; zero disables withdrawals. Destination
+    /// and delay come from the vault's `WithdrawalPolicy`.
+    pub withdrawal_epoch_cap: u64,
+    pub pending_epoch_cap: Option<u64>,
+    pub pending_cap_effective_at: i64,
+    pub pending_withdrawal: Option<PendingWithdrawal>,
+    pub withdrawal_epoch: u64,
+    pub epoch_withdrawn: u64,
+    pub total_withdrawn: u64,
+    /// Per-settlement fee ceiling in token units; `None` leaves only `fee_bps` in force.
+    pub fee_cap: Option<u64>,
+    pub pending_fee_cap: Option<u64>,
+    pub pending_fee_cap_effective_at: i64,
+}
+
+impl TokenVaultState {
+    pub const SEED: &'static [u8] = b"token-vault";
+
+    pub fn space() -> usize {
+        8 + 32
+            + 32
+            + 32
+            + 1
+            + 8
+            + 8
+            + 8
+            + 8
+            + 8
+            + (1 + 8)
+            + 8
+            + (1 + PendingWithdrawal::space())
+            + 8
+            + 8
+            + 8
+            + (1 + 8)
+            + (1 + 8)
+            + 8
+    }
+
+    pub fn available_funds(&self, token_account: &TokenAccount) -> u64 {
+        token_account.amount.saturating_sub(self.total_locked)
+    }
+
+    /// Promotes a queued cap increase once its delay has elapsed.
+    pub fn refresh_withdrawal_cap(&mut self, now: i64) {
+        if let Some(pending) = self.pending_epoch_cap {
+            if now >= self.pending_cap_effective_at {
+                self.withdrawal_epoch_cap = pending;
+                self.pending_epoch_cap = None;
+                self.pending_cap_effective_at = 0;
+            }
+        }
+    }
+
+    /// Promotes a queued fee cap increase once its delay has elapsed.
+    pub fn refresh_fee_cap(&mut self, now: i64) {
+        if let Some(pending) = self.pending_fee_cap {
+            if now >= self.pending_fee_cap_effective_at {
+                self.fee_cap = Some(pending);
+                self.pending_fee_cap = None;
+                self.pending_fee_cap_effective_at = 0;
+            }
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoidReason {
+    BattleCancelled,
+    JoinTimeout,
+    ForceSettled,
+    Operator,
+    /// The battle finished without a winner; house reservations return to the treasury.
+    Draw,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WagerMode {
+    /// House treasury lamports are reserved against `available_funds`.
+    House,
+    /// Each player deposits an equal stake into the wager's escrow PDA.
+    Escrow,
+}
+
+/// Leading fields of battle_core's `BattleState`, up to and including `winner`.
+/// battle_core depends on this crate, so the layout is mirrored rather than imported;
+/// keep it in sync with the battle_core account definition.
+#[derive(AnchorDeserialize, Clone, Debug)]
+pub struct BattleSnapshot {
+    pub platform: Pubkey,
+    pub battle_id: u64,
+    pub bump: u8,
+    pub host: Pubkey,
+    pub host_pet: Pubkey,
+    pub challenger: Option<Pubkey>,
+    pub challenger_pet: Option<Pubkey>,
+    pub status: BattleSnapshotStatus,
+    pub turn_index: u8,
+    pub host_submission: Option<TurnSubmissionSnapshot>,
+    pub challenger_submission: Option<TurnSubmissionSnapshot>,
+    pub winner: Option<Pubkey>,
+}
+
+#[derive(AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BattleSnapshotStatus {
+    Waiting,
+    Active,
+    Completed,
+    Cancelled,
+}
+
+#[derive(AnchorDeserialize, Clone, Debug)]
+pub struct TurnSubmissionSnapshot {
+    pub commitment: [u8; 32],
+    pub move_type: Option<u8>,
+    pub submitted_by: Pubkey,
+    pub submitted_at: i64,
+}
+
+impl BattleSnapshot {
+    pub const SEED: &'static [u8] = b"battle";
+
+    /// Loads `account` as a battle owned by the configured battle program, created by the
+    /// platform PDA that acts as this vault's battle authority.
+    pub fn load(account: &AccountInfo, vault: &VaultState) -> Result<Self> {
+        let program = vault
+            .battle_program
+            .ok_or(VaultError::BattleProgramNotConfigured)?;
+        require_keys_eq!(*account.owner, program, VaultError::InvalidBattleAccount);
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() > 8 && data[..8] == Self::discriminator(),
+            VaultError::InvalidBattleAccount
+        );
+        let battle = Self::deserialize(&mut &data[8..])
+            .map_err(|_| error!(VaultError::InvalidBattleAccount))?;
+        require!(
+            Some(battle.platform) == vault.battle_authority,
+            VaultError::InvalidBattleAccount
+        );
+        let expected = Pubkey::create_program_address(
+            &[
+                Self::SEED,
+                battle.platform.as_ref(),
+                &battle.battle_id.to_le_bytes(),
+                &[battle.bump],
+            ],
+            &program,
+        )
+        .map_err(|_| error!(VaultError::InvalidBattleAccount))?;
+        require_keys_eq!(expected, account.key(), VaultError::InvalidBattleAccount);
+        Ok(battle)
+    }
+
+    /// Whether the battle account has been closed by battle_core (e.g. a host cancellation).
+    pub fn is_closed(account: &AccountInfo) -> bool {
+        account.lamports() == 0 && account.data_is_empty()
+    }
+
+    fn discriminator() -> [u8; 8] {
+        let mut discriminator = [0u8; 8];
+        discriminator.copy_from_slice(&hash(b"account:BattleState").to_bytes()[..8]);
+        discriminator
+    }
+
+    pub fn check_lock(&self, params: &LockWagerParams) -> Result<()> {
+        require!(
+            self.status == BattleSnapshotStatus::Waiting,
+            VaultError::InvalidBattleStatus
+        );
+        require_keys_eq!(params.primary_recipient, self.host, VaultError::InvalidRecipient);
+        require!(
+            params.secondary_recipient.is_none()
+                || params.secondary_recipient == self.challenger,
+            VaultError::InvalidRecipient
+        );
+        Ok(())
+    }
+
+    pub fn check_secondary_recipient(&self, recipient: &Pubkey) -> Result<()> {
+        require!(
+            self.status == BattleSnapshotStatus::Active,
+            VaultError::InvalidBattleStatus
+        );
+        require!(self.challenger == Some(*recipient), VaultError::InvalidRecipient);
+        Ok(())
+    }
+
+    /// Voids are only for battles that ended without a winner (cancellations, force settles).
+    pub fn check_void(&self) -> Result<()> {
+        require!(
+            matches!(
+                self.status,
+                BattleSnapshotStatus::Completed | BattleSnapshotStatus::Cancelled
+            ),
+            VaultError::InvalidBattleStatus
+        );
+        require!(self.winner.is_none(), VaultError::PayoutNotToWinner);
+        Ok(())
+    }
+
+    /// Settlement requires a finished battle; with a recorded winner, only the winner
+    /// may receive a non-zero payout.
+    pub fn check_payout(
+        &self,
+        wager: &WagerState,
+        primary_amount: u64,
+        secondary_amount: u64,
+    ) -> Result<()> {
+        require!(
+            matches!(
+                self.status,
+                BattleSnapshotStatus::Completed | BattleSnapshotStatus::Cancelled
+            ),
+            VaultError::InvalidBattleStatus
+        );
+        if let Some(winner) = self.winner {
+            require!(
+                primary_amount == 0 || wager.recipient_primary == winner,
+                VaultError::PayoutNotToWinner
+            );
+            require!(
+                secondary_amount == 0 || wager.recipient_secondary == Some(winner),
+                VaultError::PayoutNotToWinner
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Mirror of battle_core's `PlayerLimits`, kept in sync the same way as [`BattleSnapshot`].
+#[derive(AnchorDeserialize, Clone, Debug)]
+pub struct PlayerLimitsSnapshot {
+    pub player: Pubkey,
+    pub platform: Pubkey,
+    pub bump: u8,
+    pub excluded_until: i64,
+}
+
+impl PlayerLimitsSnapshot {
+    pub const SEED: &'static [u8] = b"player-limits";
+
+    /// Rejects a player whose battle_core self-exclusion is still running. `account` is the
+    /// seed-checked PDA; a player who never created one has no exclusion to enforce.
+    pub fn ensure_not_excluded(account: &AccountInfo, vault: &VaultState, now: i64) -> Result<()> {
+        if account.data_is_empty() {
+            return Ok(());
+        }
+        let program = vault
+            .battle_program
+            .ok_or(VaultError::BattleProgramNotConfigured)?;
+        require_keys_eq!(*account.owner, program, VaultError::InvalidSelfExclusion);
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() > 8 && data[..8] == Self::discriminator(),
+            VaultError::InvalidSelfExclusion
+        );
+        let limits = Self::deserialize(&mut &data[8..])
+            .map_err(|_| error!(VaultError::InvalidSelfExclusion))?;
+        require!(now >= limits.excluded_until, VaultError::PlayerSelfExcluded);
+        Ok(())
+    }
+
+    fn discriminator() -> [u8; 8] {
+        let mut discriminator = [0u8; 8];
+        discriminator.copy_from_slice(&hash(b"account:PlayerLimits").to_bytes()[..8]);
+        discriminator
+    }
+}
+
+/// Mirror of battle_core's `PlayerAttestation`, kept in sync the same way as
+/// [`BattleSnapshot`].
+#[derive(AnchorDeserialize, Clone, Debug)]
+pub struct AttestationSnapshot {
+    pub platform: Pubkey,
+    pub player: Pubkey,
+    pub bump: u8,
+    pub attester: Pubkey,
+    pub jurisdiction: [u8; 2],
+    pub issued_at: i64,
+    pub expires_at: i64,
+    pub revoked: bool,
+}
+
+impl AttestationSnapshot {
+    pub const SEED: &'static [u8] = b"attestation";
+
+    /// Requires `account` to be a current attestation for `player` from the vault's
+    /// attester. A no-op while the vault has no attester configured.
+    pub fn check(
+        account: Option<&AccountInfo>,
+        vault: &VaultState,
+        player: &Pubkey,
+        now: i64,
+    ) -> Result<()> {
+        let Some(attester) = vault.attester else {
+            return Ok(());
+        };
+        let account = account.ok_or(VaultError::AttestationRequired)?;
+        let program = vault
+            .battle_program
+            .ok_or(VaultError::BattleProgramNotConfigured)?;
+        require_keys_eq!(*account.owner, program, VaultError::InvalidAttestation);
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() > 8 && data[..8] == Self::discriminator(),
+            VaultError::InvalidAttestation
+        );
+        let attestation = Self::deserialize(&mut &data[8..])
+            .map_err(|_| error!(VaultError::InvalidAttestation))?;
+        require!(
+            Some(attestation.platform) == vault.battle_authority,
+            VaultError::InvalidAttestation
+        );
+        require_keys_eq!(attestation.player, *player, VaultError::InvalidAttestation);
+        let expected = Pubkey::create_program_address(
+            &[
+                Self::SEED,
+                attestation.platform.as_ref(),
+                attestation.player.as_ref(),
+                &[attestation.bump],
+            ],
+            &program,
+        )
+        .map_err(|_| error!(VaultError::InvalidAttestation))?;
+        require_keys_eq!(expected, account.key(), VaultError::InvalidAttestation);
+        require_keys_eq!(attestation.attester, attester, VaultError::AttestationRequired);
+        require!(!attestation.revoked, VaultError::AttestationRevoked);
+        require!(now < attestation.expires_at, VaultError::AttestationExpired);
+        Ok(())
+    }
+
+    fn discriminator() -> [u8; 8] {
+        let mut discriminator = [0u8; 8];
+        discriminator.copy_from_slice(&hash(b"account:PlayerAttestation").to_bytes()[..8]);
+        discriminator
+    }
+}
+
+pub const MAX_APPROVERS: usize = 5;
+/// Reason code recorded when `reconcile` pauses an under-collateralised vault.
+pub const PAUSE_REASON_UNDER_COLLATERALISED: u16 = u16::MAX;
+pub const MAX_FEE_BPS: u16 = 1_000;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Platform rake taken from settlements.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeConfig {
+    /// Fee on the wager's `locked_amount`, in basis points.
+    pub fee_bps: u16,
+    /// Optional ceiling on the lamport fee charged per wager; token vaults carry their own cap.
+    pub fee_cap: Option<u64>,
+    pub fee_recipient: Option<Pubkey>,
+}
+
+impl FeeConfig {
+    pub const fn space() -> usize {
+        2 + (1 + 8) + (1 + 32)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.fee_bps <= MAX_FEE_BPS, VaultError::InvalidFeeConfig);
+        require!(
+            self.fee_bps == 0 || self.fee_recipient.is_some(),
+            VaultError::InvalidFeeConfig
+        );
+        Ok(())
+    }
+
+    /// Field-wise strictest of two configs. Differing recipients stop the rake entirely until
+    /// the recipient change clears its delay.
+    pub fn tightest(&self, other: &Self) -> Self {
+        let fee_recipient = self
+            .fee_recipient
+            .filter(|recipient| other.fee_recipient == Some(*recipient));
+        let fee_cap = match (self.fee_cap, other.fee_cap) {
+            (Some(cap), Some(other_cap)) => Some(cap.min(other_cap)),
+            (cap, None) | (None, cap) => cap,
+        };
+        Self {
+            fee_bps: if fee_recipient.is_some() {
+                self.fee_bps.min(other.fee_bps)
+            } else {
+                0
+            },
+            fee_cap,
+            fee_recipient,
+        }
+    }
+
+    /// Whether `self` relaxes `current`: a higher rate, a higher or removed cap, or a new
+    /// recipient.
+    pub fn loosens(&self, current: &Self) -> bool {
+        (self.fee_recipient.is_some() && self.fee_recipient != current.fee_recipient)
+            || self.fee_bps > current.fee_bps
+            || self.fee_cap.unwrap_or(u64::MAX) > current.fee_cap.unwrap_or(u64::MAX)
+    }
+
+    /// Fee owed on settling `wager` with the given payouts. Escrow settlements that hand
+    /// each stake straight back (cancellations, refunds, even draws) are not raked.
+    pub fn fee_for(&self, wager: &WagerState, primary_amount: u64, secondary_amount: u64) -> u64 {
+        let is_refund = wager.mode == WagerMode::Escrow
+            && primary_amount == wager.primary_stake
+            && secondary_amount == wager.secondary_stake;
+        if is_refund || self.fee_bps == 0 {
+            return 0;
+        }
+        let rake = wager.locked_amount as u128 * self.fee_bps as u128 / BPS_DENOMINATOR as u128;
+        let rake = self.fee_cap.map_or(rake as u64, |cap| (rake as u64).min(cap));
+        rake.min(primary_amount.saturating_add(secondary_amount))
+    }
 }
 
 #[account]
-pub struct WagerState {
+pub struct ProposalState {
     pub vault: Pubkey,
-    pub battle: Pubkey,
+    pub proposal_id: u64,
     pub bump: u8,
-    pub locked_amount: u64,
-    pub recipient_primary: Pubkey,
-    pub recipient_secondary: Option<Pubkey>,
-    pub locked_at: i64,
-    pub settled_at: Option<i64>,
-    pub settled: bool,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    pub approvals: Vec<Pubkey>,
+    pub executed: bool,
+    pub created_at: i64,
 }
 
-impl WagerState {
-    pub const SEED: &'static [u8] = b"wager-state";
+impl ProposalState {
+    pub const SEED: &'static [u8] = b"proposal";
 
     pub fn space() -> usize {
-        8
-            + 32
-            + 32
-            + 1
-            + 8
-            + 32
-            + (1 + 32)
-            + 8
-            + (1 + 8)
-            + 1
+        8 + 32 + 8 + 1 + 32 + ProposalAction::space() + 4 + 32 * MAX_APPROVERS + 1 + 8
+    }
+}
+
+/// Sensitive vault actions that require multisig approval.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ProposalAction {
+    SettlePayout {
+        wager_state: Pubkey,
+        primary_amount: u64,
+        secondary_amount: Option<u64>,
+    },
+    EmergencyWithdraw {
+        amount: u64,
+        destination: Pubkey,
+    },
+    SetApprovers {
+        approvers: Vec<Pubkey>,
+        threshold: u8,
+    },
+    ResolveFrozenFunds {
+        wallet: Pubkey,
+        resolution: FrozenFundsResolution,
+    },
+    /// Reserves treasury funds for a house-mode wager on `battle`.
+    LockHouseWager {
+        battle: Pubkey,
+        amount: u64,
+    },
+    SetWithdrawalPolicy {
+        policy: WithdrawalPolicy,
+    },
+    SetFeeConfig {
+        fee_config: FeeConfig,
+    },
+    SetPauser {
+        pauser: Option<Pubkey>,
+    },
+    TransferAuthority {
+        new_authority: Pubkey,
+    },
+    SetTokenWithdrawalCap {
+        mint: Pubkey,
+        epoch_cap: u64,
+    },
+    WithdrawToken {
+        mint: Pubkey,
+        amount: u64,
+        destination: Pubkey,
+    },
+    SetTokenFeeCap {
+        mint: Pubkey,
+        fee_cap: u64,
+    },
+}
+
+impl ProposalAction {
+    pub fn space() -> usize {
+        1 + (4 + 32 * MAX_APPROVERS + 1)
+            .max(32 + 8 + 1 + 8)
+            .max(WithdrawalPolicy::space())
+            .max(FeeConfig::space())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            ProposalAction::SettlePayout { .. } => Ok(()),
+            ProposalAction::EmergencyWithdraw { amount, .. }
+            | ProposalAction::LockHouseWager { amount, .. }
+            | ProposalAction::WithdrawToken { amount, .. } => {
+                require!(*amount > 0, VaultError::InvalidAmount);
+                Ok(())
+            }
+            ProposalAction::SetApprovers { approvers, threshold } => {
+                validate_approver_set(approvers, *threshold)
+            }
+            ProposalAction::ResolveFrozenFunds { .. } => Ok(()),
+            ProposalAction::SetWithdrawalPolicy { policy } => policy.validate(),
+            ProposalAction::SetFeeConfig { fee_config } => fee_config.validate(),
+            ProposalAction::SetPauser { .. }
+            | ProposalAction::SetTokenWithdrawalCap { .. }
+            | ProposalAction::SetTokenFeeCap { .. } => Ok(()),
+            ProposalAction::TransferAuthority { new_authority } => {
+                require!(
+                    *new_authority != Pubkey::default(),
+                    VaultError::InvalidPendingAuthority
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+pub const DEFAULT_WITHDRAWAL_DELAY_SECS: i64 = 48 * 60 * 60;
+pub const MIN_WITHDRAWAL_DELAY_SECS: i64 = 60 * 60;
+
+/// Guardrails for treasury withdrawals. A zero `epoch_cap` disables withdrawals entirely.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WithdrawalPolicy {
+    pub treasury_destination: Option<Pubkey>,
+    pub delay_secs: i64,
+    /// Maximum lamports withdrawn per cluster epoch.
+    pub epoch_cap: u64,
+}
+
+impl WithdrawalPolicy {
+    pub const fn space() -> usize {
+        1 + 32 + 8 + 8
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.delay_secs >= MIN_WITHDRAWAL_DELAY_SECS,
+            VaultError::InvalidWithdrawalPolicy
+        );
+        require!(
+            self.treasury_destination != Some(Pubkey::default()),
+            VaultError::InvalidWithdrawalPolicy
+        );
+        Ok(())
+    }
+
+    /// Field-wise strictest of two policies. Differing destinations collapse to none, so
+    /// nothing can be withdrawn while a destination change is queued.
+    pub fn tightest(&self, other: &Self) -> Self {
+        Self {
+            treasury_destination: self
+                .treasury_destination
+                .filter(|destination| other.treasury_destination == Some(*destination)),
+            delay_secs: self.delay_secs.max(other.delay_secs),
+            epoch_cap: self.epoch_cap.min(other.epoch_cap),
+        }
+    }
+
+    /// Whether `self` relaxes `current`: a new destination, a shorter delay or a higher cap.
+    pub fn loosens(&self, current: &Self) -> bool {
+        (self.treasury_destination.is_some()
+            && self.treasury_destination != current.treasury_destination)
+            || self.delay_secs < current.delay_secs
+            || self.epoch_cap > current.epoch_cap
+    }
+}
+
+impl Default for WithdrawalPolicy {
+    fn default() -> Self {
+        Self {
+            treasury_destination: None,
+            delay_secs: DEFAULT_WITHDRAWAL_DELAY_SECS,
+            epoch_cap: 0,
+        }
+    }
+}
+
+pub const DEFAULT_MAX_LOCK_DURATION_SECS: i64 = 7 * 24 * 60 * 60;
+/// Shortest allowed lock duration; kept above battle_core's `TimeoutConfig::MAX_TIMEOUT_SECS`
+/// so no join, turn or oracle window can outlast a wager lock.
+pub const MIN_MAX_LOCK_DURATION_SECS: i64 = 4 * 24 * 60 * 60;
+/// Daily, weekly and monthly limit windows.
+pub const LIMIT_PERIOD_SECS: [i64; 3] = [24 * 60 * 60, 7 * 24 * 60 * 60, 30 * 24 * 60 * 60];
+/// How long a looser wager limit waits before it replaces the current one.
+pub const LIMIT_INCREASE_COOLING_SECS: i64 = 24 * 60 * 60;
+pub const DEFAULT_EXPIRY_CRANK_REWARD: u64 = 10_000;
+
+/// How long a wager may stay locked before anyone can expire it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExpiryPolicy {
+    pub max_lock_duration_secs: i64,
+    /// Lamports paid to the cranker from the expired wager account's rent.
+    pub crank_reward: u64,
+}
+
+impl ExpiryPolicy {
+    pub const fn space() -> usize {
+        8 + 8
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.max_lock_duration_secs >= MIN_MAX_LOCK_DURATION_SECS,
+            VaultError::InvalidExpiryPolicy
+        );
+        require!(
+            self.crank_reward <= Rent::get()?.minimum_balance(WagerState::space()),
+            VaultError::InvalidExpiryPolicy
+        );
+        Ok(())
+    }
+}
+
+impl Default for ExpiryPolicy {
+    fn default() -> Self {
+        Self {
+            max_lock_duration_secs: DEFAULT_MAX_LOCK_DURATION_SECS,
+            crank_reward: DEFAULT_EXPIRY_CRANK_REWARD,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PendingWithdrawal {
+    pub amount: u64,
+    pub requested_at: i64,
+    pub executable_at: i64,
+}
+
+impl PendingWithdrawal {
+    pub const fn space() -> usize {
+        8 + 8 + 8
     }
 }
 
@@ -382,6 +3834,14 @@ pub struct TreasuryDeposited {
     pub vault: Pubkey,
     pub funder: Pubkey,
     pub amount: u64,
+    pub mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct TokenVaultInitialized {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
 }
 
 #[event]
@@ -391,14 +3851,244 @@ pub struct WagerLocked {
     pub amount: u64,
     pub primary_recipient: Pubkey,
     pub secondary_recipient: Option<Pubkey>,
+    pub mode: WagerMode,
+    pub mint: Option<Pubkey>,
 }
 
 #[event]
 pub struct PayoutSettled {
     pub vault: Pubkey,
     pub battle: Pubkey,
+    /// Net amounts paid to recipients after the fee.
     pub primary_amount: u64,
     pub secondary_amount: u64,
+    pub mint: Option<Pubkey>,
+    pub fee: u64,
+}
+
+#[event]
+pub struct WagerVoided {
+    pub vault: Pubkey,
+    pub battle: Pubkey,
+    pub reason: VoidReason,
+    pub primary_refund: u64,
+    pub secondary_refund: u64,
+    pub mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct ExpiryPolicyUpdated {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub max_lock_duration_secs: i64,
+    pub crank_reward: u64,
+}
+
+#[event]
+pub struct WagerLimitsUpdated {
+    pub vault: Pubkey,
+    pub player: Pubkey,
+    pub limits: LimitSchedule,
+    pub pending_limits: Option<LimitSchedule>,
+    pub pending_effective_at: i64,
+}
+
+#[event]
+pub struct DenyListUpdated {
+    pub vault: Pubkey,
+    pub wallet: Pubkey,
+    pub denied: bool,
+    pub reason_code: u16,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct PayoutFrozen {
+    pub vault: Pubkey,
+    pub battle: Pubkey,
+    pub wallet: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FrozenFundsResolved {
+    pub vault: Pubkey,
+    pub wallet: Pubkey,
+    pub resolution: FrozenFundsResolution,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct WagerExpired {
+    pub vault: Pubkey,
+    pub battle: Pubkey,
+    pub cranker: Pubkey,
+    pub crank_reward: u64,
+    pub primary_refund: u64,
+    pub secondary_refund: u64,
+    pub mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct SecondaryRecipientAssigned {
+    pub vault: Pubkey,
+    pub battle: Pubkey,
+    pub recipient: Pubkey,
+    pub stake: u64,
+}
+
+#[event]
+pub struct AuthorityTransferProposed {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferCancelled {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+}
+
+#[event]
+pub struct AuthorityTransferAccepted {
+    pub vault: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct AttesterUpdated {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub attester: Option<Pubkey>,
+}
+
+#[event]
+pub struct PauserUpdated {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub pauser: Option<Pubkey>,
+}
+
+#[event]
+pub struct VaultPaused {
+    pub vault: Pubkey,
+    pub pauser: Pubkey,
+    /// Off-chain compliance reason code for the halt.
+    pub reason_code: u16,
+    pub paused_at: i64,
+}
+
+#[event]
+pub struct VaultReconciled {
+    pub vault: Pubkey,
+    pub caller: Pubkey,
+    pub actual_balance: u64,
+    pub expected_balance: u64,
+    /// `actual_balance - expected_balance`; negative means lamports are missing.
+    pub discrepancy: i64,
+    pub total_locked: u64,
+    pub under_collateralised: bool,
+    pub reconciled_at: i64,
+}
+
+#[event]
+pub struct VaultUnpaused {
+    pub vault: Pubkey,
+    pub pauser: Pubkey,
+    pub unpaused_at: i64,
+}
+
+#[event]
+pub struct FeeConfigUpdated {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub fee_bps: u16,
+    pub fee_cap: Option<u64>,
+    pub fee_recipient: Option<Pubkey>,
+    pub pending_fee_config: Option<FeeConfig>,
+    pub pending_effective_at: i64,
+}
+
+#[event]
+pub struct TokenFeeCapUpdated {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub fee_cap: Option<u64>,
+    pub pending_fee_cap: Option<u64>,
+    pub pending_effective_at: i64,
+}
+
+#[event]
+pub struct TokenWithdrawalCapUpdated {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub epoch_cap: u64,
+    pub pending_epoch_cap: Option<u64>,
+    pub pending_effective_at: i64,
+}
+
+#[event]
+pub struct WithdrawalPolicyUpdated {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub treasury_destination: Option<Pubkey>,
+    pub delay_secs: i64,
+    pub epoch_cap: u64,
+    pub pending_policy: Option<WithdrawalPolicy>,
+    pub pending_effective_at: i64,
+}
+
+#[event]
+pub struct EmergencyWithdrawalQueued {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub executable_at: i64,
+    pub mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct EmergencyWithdrawalCancelled {
+    pub vault: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub amount: u64,
+    pub mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct ApproversUpdated {
+    pub vault: Pubkey,
+    pub approvers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub vault: Pubkey,
+    pub proposal: Pubkey,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct ProposalApproved {
+    pub vault: Pubkey,
+    pub proposal: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub vault: Pubkey,
+    pub proposal: Pubkey,
+    pub approvals: u8,
 }
 
 #[event]
@@ -406,6 +4096,7 @@ pub struct EmergencyWithdrawal {
     pub vault: Pubkey,
     pub authority: Pubkey,
     pub amount: u64,
+    pub mint: Option<Pubkey>,
 }
 
 #[error_code]
@@ -428,4 +4119,274 @@ pub enum VaultError {
     SettlementExceedsLock,
     #[msg("Secondary payout amount must be zero when no secondary recipient is stored")]
     SettlementSecondaryMismatch,
-}
\ No newline at end of file
+    #[msg("Secondary recipient has already been assigned for this wager")]
+    SecondaryRecipientAlreadySet,
+    #[msg("Escrow accounts are required for escrow-mode wagers")]
+    EscrowAccountMissing,
+    #[msg("Stake must cover the rent-exempt minimum of the escrow account")]
+    StakeBelowRentMinimum,
+    #[msg("Escrow-mode settlements must release the full pot")]
+    EscrowNotFullyReleased,
+    #[msg("Vault is not paused")]
+    VaultNotPaused,
+    #[msg("No authority transfer is pending")]
+    NoPendingAuthority,
+    #[msg("Signer or proposed key is not a valid pending authority")]
+    InvalidPendingAuthority,
+    #[msg("Withdrawal delay is too short or the treasury destination is invalid")]
+    InvalidWithdrawalPolicy,
+    #[msg("No treasury destination has been registered")]
+    TreasuryDestinationNotSet,
+    #[msg("Destination does not match the registered treasury destination")]
+    InvalidTreasuryDestination,
+    #[msg("An emergency withdrawal is already queued")]
+    WithdrawalAlreadyPending,
+    #[msg("No emergency withdrawal is queued")]
+    NoPendingWithdrawal,
+    #[msg("The withdrawal timelock has not elapsed")]
+    WithdrawalTimelockActive,
+    #[msg("Withdrawal exceeds the per-epoch cap")]
+    WithdrawalCapExceeded,
+    #[msg("Approver set is too large, has duplicates, or the threshold is out of range")]
+    InvalidApproverSet,
+    #[msg("An approved multisig proposal is required for this action")]
+    ProposalRequired,
+    #[msg("Proposal does not authorise this action")]
+    ProposalActionMismatch,
+    #[msg("Proposal has not reached the approval threshold")]
+    ProposalThresholdNotMet,
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Approver has already approved this proposal")]
+    ProposalAlreadyApproved,
+    #[msg("Mint is not the configured MATKA token mint")]
+    UnsupportedMint,
+    #[msg("Token wagers only support house mode")]
+    TokenEscrowUnsupported,
+    #[msg("Wager denomination does not match this settlement instruction")]
+    WagerDenominationMismatch,
+    #[msg("Fee exceeds the maximum or no fee recipient is configured")]
+    InvalidFeeConfig,
+    #[msg("A fee recipient account is required for this settlement")]
+    FeeRecipientMissing,
+    #[msg("Fee recipient does not match the vault's fee config")]
+    FeeRecipientMismatch,
+    #[msg("No battle program is configured for this vault")]
+    BattleProgramNotConfigured,
+    #[msg("Account is not a battle created by the configured battle program and platform")]
+    InvalidBattleAccount,
+    #[msg("Battle is not in the required status for this action")]
+    InvalidBattleStatus,
+    #[msg("Payouts may only go to the battle's recorded winner")]
+    PayoutNotToWinner,
+    #[msg("Lock duration is below the minimum or the crank reward exceeds wager rent")]
+    InvalidExpiryPolicy,
+    #[msg("Wager has not exceeded the maximum lock duration")]
+    WagerNotExpired,
+    #[msg("Stake would exceed the player's stake limit")]
+    StakeLimitExceeded,
+    #[msg("Stake could exceed the player's net loss limit")]
+    LossLimitExceeded,
+    #[msg("Player limits accounts are required to settle escrow wagers")]
+    PlayerLimitsMissing,
+    #[msg("Account is not a player wager limits account of this vault")]
+    InvalidPlayerLimits,
+    #[msg("A current attestation from the vault's attester is required")]
+    AttestationRequired,
+    #[msg("Account is not a battle_core attestation for this player")]
+    InvalidAttestation,
+    #[msg("Attestation has been revoked")]
+    AttestationRevoked,
+    #[msg("Attestation has expired")]
+    AttestationExpired,
+    #[msg("Wallet is on the vault deny list")]
+    WalletDenied,
+    #[msg("A deny list entry account is required for the secondary recipient")]
+    DenyListEntryMissing,
+    #[msg("Frozen payouts must be resolved before the wallet can be removed")]
+    FrozenFundsOutstanding,
+    #[msg("No frozen funds are held for this wallet")]
+    NoFrozenFunds,
+    #[msg("House-mode locks need the vault authority's signature or an approved proposal")]
+    HouseLockNotApproved,
+    #[msg("Account is not the player's battle_core limits account")]
+    InvalidSelfExclusion,
+    #[msg("Player is self-excluded")]
+    PlayerSelfExcluded,
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(destination: Option<Pubkey>, delay_secs: i64, epoch_cap: u64) -> WithdrawalPolicy {
+        WithdrawalPolicy {
+            treasury_destination: destination,
+            delay_secs,
+            epoch_cap,
+        }
+    }
+
+    #[test]
+    fn withdrawal_policy_loosening_is_detected_per_field() {
+        let treasury = Pubkey::new_unique();
+        let current = policy(Some(treasury), 48 * 3600, 1_000);
+        assert!(!current.loosens(&current));
+        assert!(policy(Some(Pubkey::new_unique()), 48 * 3600, 1_000).loosens(&current));
+        assert!(policy(Some(treasury), 3600, 1_000).loosens(&current));
+        assert!(policy(Some(treasury), 48 * 3600, u64::MAX).loosens(&current));
+        assert!(!policy(None, 72 * 3600, 10).loosens(&current));
+        assert!(policy(Some(treasury), 48 * 3600, 1).loosens(&policy(None, 48 * 3600, 1)));
+    }
+
+    #[test]
+    fn withdrawal_policy_tightest_keeps_the_strictest_fields() {
+        let treasury = Pubkey::new_unique();
+        let current = policy(Some(treasury), 48 * 3600, 1_000);
+        let requested = policy(Some(Pubkey::new_unique()), 3600, 500);
+        assert_eq!(current.tightest(&requested), policy(None, 48 * 3600, 500));
+        let same_destination = policy(Some(treasury), 72 * 3600, 5_000);
+        assert_eq!(current.tightest(&same_destination), policy(Some(treasury), 72 * 3600, 1_000));
+    }
+
+    fn wager_limits(daily: PeriodLimit) -> PlayerWagerLimits {
+        PlayerWagerLimits {
+            vault: Pubkey::new_unique(),
+            player: Pubkey::new_unique(),
+            bump: 255,
+            limits: LimitSchedule { daily, ..LimitSchedule::default() },
+            pending_limits: None,
+            pending_effective_at: 0,
+            usage: [PeriodUsage::default(); 3],
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn daily_cap(max_stake: Option<u64>, max_net_loss: Option<u64>) -> LimitSchedule {
+        LimitSchedule { daily: PeriodLimit { max_stake, max_net_loss }, ..LimitSchedule::default() }
+    }
+
+    #[test]
+    fn wager_limits_count_stakes_and_losses_per_window() {
+        let mut limits =
+            wager_limits(PeriodLimit { max_stake: Some(1_000), max_net_loss: Some(600) });
+        limits.record_stake(500).unwrap();
+        limits.record_result(500, 0).unwrap();
+        assert_eq!(limits.record_stake(200), Err(VaultError::LossLimitExceeded.into()));
+        limits.record_stake(100).unwrap();
+        limits.record_result(100, 300).unwrap();
+        assert_eq!(limits.usage[0].net_loss, 300);
+        assert_eq!(limits.record_stake(401), Err(VaultError::StakeLimitExceeded.into()));
+        limits.record_stake(300).unwrap();
+        assert_eq!(limits.usage[0].staked, 900);
+        assert_eq!(limits.usage[2].staked, 900);
+    }
+
+    #[test]
+    fn wager_limit_windows_roll_over_independently() {
+        let mut limits = wager_limits(PeriodLimit { max_stake: Some(1_000), max_net_loss: None });
+        limits.record_stake(1_000).unwrap();
+        limits.record_result(1_000, 0).unwrap();
+        let day = LIMIT_PERIOD_SECS[0];
+        limits.refresh(day - 1);
+        assert_eq!(limits.record_stake(1), Err(VaultError::StakeLimitExceeded.into()));
+        limits.refresh(day);
+        assert_eq!(limits.usage[0], PeriodUsage { window_start: day, staked: 0, net_loss: 0 });
+        assert_eq!(limits.usage[1].staked, 1_000);
+        assert_eq!(limits.usage[2].net_loss, 1_000);
+        limits.record_stake(1_000).unwrap();
+    }
+
+    #[test]
+    fn wager_limit_decreases_apply_at_once_and_increases_wait() {
+        let mut limits = wager_limits(PeriodLimit { max_stake: Some(1_000), max_net_loss: None });
+        limits.request_limits(daily_cap(Some(400), None), 10).unwrap();
+        assert_eq!(limits.limits, daily_cap(Some(400), None));
+        assert_eq!(limits.pending_limits, None);
+        assert_eq!(limits.record_stake(401), Err(VaultError::StakeLimitExceeded.into()));
+
+        // Raising the stake cap while adding a loss cap applies the loss cap immediately.
+        let raised = daily_cap(Some(2_000), Some(500));
+        limits.request_limits(raised, 20).unwrap();
+        assert_eq!(limits.limits, daily_cap(Some(400), Some(500)));
+        assert_eq!(limits.pending_limits, Some(raised));
+        let effective_at = 20 + LIMIT_INCREASE_COOLING_SECS;
+        assert_eq!(limits.pending_effective_at, effective_at);
+        limits.refresh(effective_at - 1);
+        assert_eq!(limits.limits, daily_cap(Some(400), Some(500)));
+        limits.refresh(effective_at);
+        assert_eq!(limits.limits, raised);
+        assert_eq!(limits.pending_limits, None);
+
+        // A tightening request cancels an increase that is still cooling.
+        limits.request_limits(daily_cap(None, None), effective_at).unwrap();
+        assert!(limits.pending_limits.is_some());
+        limits.request_limits(daily_cap(Some(100), Some(100)), effective_at).unwrap();
+        assert_eq!(limits.limits, daily_cap(Some(100), Some(100)));
+        assert_eq!(limits.pending_limits, None);
+        assert_eq!(limits.pending_effective_at, 0);
+    }
+
+    #[test]
+    fn token_withdrawal_cap_increase_waits_for_its_delay() {
+        let mut token_vault = TokenVaultState {
+            vault: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            token_account: Pubkey::new_unique(),
+            bump: 255,
+            total_deposited: 0,
+            total_locked: 0,
+            total_settled: 0,
+            total_fees: 0,
+            withdrawal_epoch_cap: 100,
+            pending_epoch_cap: Some(1_000),
+            pending_cap_effective_at: 500,
+            pending_withdrawal: None,
+            withdrawal_epoch: 0,
+            epoch_withdrawn: 0,
+            total_withdrawn: 0,
+            fee_cap: None,
+            pending_fee_cap: None,
+            pending_fee_cap_effective_at: 0,
+        };
+        token_vault.refresh_withdrawal_cap(499);
+        assert_eq!(token_vault.withdrawal_epoch_cap, 100);
+        assert_eq!(token_vault.pending_epoch_cap, Some(1_000));
+        token_vault.refresh_withdrawal_cap(500);
+        assert_eq!(token_vault.withdrawal_epoch_cap, 1_000);
+        assert_eq!(token_vault.pending_epoch_cap, None);
+        assert_eq!(token_vault.pending_cap_effective_at, 0);
+    }
+
+    #[test]
+    fn fee_config_loosening_waits_and_tightening_applies() {
+        let recipient = Pubkey::new_unique();
+        let current = FeeConfig {
+            fee_bps: 200,
+            fee_cap: Some(1_000),
+            fee_recipient: Some(recipient),
+        };
+        let lower = FeeConfig {
+            fee_bps: 100,
+            ..current
+        };
+        assert!(!lower.loosens(&current));
+        assert_eq!(current.tightest(&lower), lower);
+        let uncapped = FeeConfig {
+            fee_cap: None,
+            ..current
+        };
+        assert!(uncapped.loosens(&current));
+        assert_eq!(current.tightest(&uncapped), current);
+        let redirected = FeeConfig {
+            fee_bps: 50,
+            fee_recipient: Some(Pubkey::new_unique()),
+            ..current
+        };
+        assert!(redirected.loosens(&current));
+        let interim = current.tightest(&redirected);
+        assert_eq!(interim.fee_bps, 0);
+        assert_eq!(interim.fee_recipient, None);
+        assert!(interim.validate().is_ok());
+    }
+}
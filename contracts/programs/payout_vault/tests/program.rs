@@ -0,0 +1,658 @@
+//! Instruction-level tests: each test runs a real instruction through the program entrypoint
+//! against in-memory accounts. Syscall stubs supply the clock and rent sysvars and execute
+//! system transfers with the runtime's checks on the source account.
+
+use std::cell::Cell;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::{ProgramResult, SUCCESS};
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::system_program;
+use anchor_lang::InstructionData;
+use payout_vault::{
+    instruction, DenyListEntry, ExpiryPolicy, FeeConfig, FrozenFundsResolution, SettlePayoutParams,
+    VaultError, VaultState, VoidReason, WagerMode, WagerState, WithdrawalPolicy,
+};
+
+const START: i64 = 1_700_000_000;
+const SOL: u64 = 1_000_000_000;
+/// battle_core `BattleStatus` discriminants.
+const ACTIVE: u8 = 1;
+const COMPLETED: u8 = 2;
+const CANCELLED: u8 = 3;
+
+thread_local! {
+    static NOW: Cell<i64> = const { Cell::new(START) };
+}
+
+fn advance_clock(secs: i64) {
+    NOW.with(|now| now.set(now.get() + secs));
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { unix_timestamp: NOW.with(Cell::get), epoch: 1, ..Clock::default() };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        assert_eq!(instruction.program_id, system_program::ID, "only system CPIs are stubbed");
+        let (tag, amount) = instruction.data.split_at(4);
+        assert_eq!(tag, 2u32.to_le_bytes(), "only system transfers are stubbed");
+        let amount = u64::from_le_bytes(amount.try_into().unwrap());
+        let find = |index: usize| {
+            let key = instruction.accounts[index].pubkey;
+            account_infos.iter().find(|info| *info.key == key).unwrap()
+        };
+        let (from, to) = (find(0), find(1));
+        let signed = from.is_signer
+            || signers_seeds.iter().any(|seeds| {
+                Pubkey::create_program_address(seeds, &payout_vault::ID).ok() == Some(*from.key)
+            });
+        if !signed {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        // The system program only debits accounts it owns that carry no data.
+        if *from.owner != system_program::ID || !from.data_is_empty() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let from_balance =
+            from.lamports().checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+        **from.try_borrow_mut_lamports()? = from_balance;
+        **to.try_borrow_mut_lamports()? += amount;
+        Ok(())
+    }
+}
+
+/// Key preceded by the original data length, as in the runtime's serialized input; `close`
+/// reads it when shrinking an account.
+#[repr(C)]
+struct KeySlot {
+    original_data_len: u32,
+    key: Pubkey,
+}
+
+fn account_with(
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+    executable: bool,
+) -> AccountInfo<'static> {
+    let slot = Box::leak(Box::new(KeySlot { original_data_len: data.len() as u32, key }));
+    // Data is prefixed by its length so `realloc` can rewrite it in place.
+    let mut buffer = (data.len() as u64).to_le_bytes().to_vec();
+    buffer.extend(data);
+    let buffer = Box::leak(buffer.into_boxed_slice());
+    AccountInfo::new(
+        &slot.key,
+        is_signer,
+        true,
+        Box::leak(Box::new(lamports)),
+        &mut buffer[8..],
+        Box::leak(Box::new(owner)),
+        executable,
+        0,
+    )
+}
+
+fn wallet(lamports: u64) -> AccountInfo<'static> {
+    account_with(Pubkey::new_unique(), system_program::ID, lamports, vec![], false, false)
+}
+
+fn wallet_at(key: Pubkey) -> AccountInfo<'static> {
+    account_with(key, system_program::ID, 0, vec![], false, false)
+}
+
+fn signer() -> AccountInfo<'static> {
+    account_with(Pubkey::new_unique(), system_program::ID, SOL, vec![], true, false)
+}
+
+/// Placeholder for an optional account left out of an instruction.
+fn none() -> AccountInfo<'static> {
+    account_with(payout_vault::ID, Pubkey::default(), 0, vec![], false, true)
+}
+
+fn system_program_account() -> AccountInfo<'static> {
+    account_with(system_program::ID, Pubkey::default(), 1, vec![], false, true)
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &payout_vault::ID)
+}
+
+fn program_account<T: AccountSerialize>(
+    key: Pubkey,
+    state: &T,
+    space: usize,
+) -> AccountInfo<'static> {
+    let mut data = Vec::with_capacity(space);
+    state.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+    let lamports = Rent::default().minimum_balance(space);
+    account_with(key, payout_vault::ID, lamports, data, false, false)
+}
+
+fn error(error: VaultError) -> ProgramError {
+    Error::from(error).into()
+}
+
+fn load<T: AccountDeserialize>(account: &AccountInfo) -> T {
+    T::try_deserialize(&mut &account.try_borrow_data().unwrap()[..]).unwrap()
+}
+
+fn process(accounts: Vec<AccountInfo<'static>>, data: impl InstructionData) -> ProgramResult {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(Stubs));
+    });
+    let accounts = Box::leak(accounts.into_boxed_slice());
+    payout_vault::entry(&payout_vault::ID, accounts, &data.data())
+}
+
+/// Vault operated by `authority`, with `platform` as battle_core's signing PDA.
+struct Vault {
+    authority: AccountInfo<'static>,
+    platform: AccountInfo<'static>,
+    battle_program: Pubkey,
+    account: AccountInfo<'static>,
+}
+
+impl Vault {
+    fn new(treasury: u64, configure: impl FnOnce(&mut VaultState)) -> Self {
+        let authority = signer();
+        let platform = signer();
+        let battle_program = Pubkey::new_unique();
+        let (key, bump) = pda(&[VaultState::SEED]);
+        let mut state = VaultState {
+            authority: authority.key(),
+            bump,
+            paused: false,
+            total_deposited: treasury,
+            total_locked: 0,
+            total_settled: 0,
+            matka_token_mint: None,
+            battle_authority: Some(platform.key()),
+            battle_program: Some(battle_program),
+            pauser: None,
+            pause_reason: None,
+            pending_authority: None,
+            withdrawal_policy: WithdrawalPolicy::default(),
+            pending_withdrawal_policy: None,
+            pending_policy_effective_at: 0,
+            pending_withdrawal: None,
+            withdrawal_epoch: 0,
+            epoch_withdrawn: 0,
+            total_withdrawn: 0,
+            approvers: vec![],
+            approval_threshold: 0,
+            next_proposal_id: 0,
+            fee_config: FeeConfig::default(),
+            pending_fee_config: None,
+            pending_fee_effective_at: 0,
+            total_fees: 0,
+            total_house_paid_out: 0,
+            expiry_policy: ExpiryPolicy::default(),
+            attester: None,
+            total_frozen: 0,
+            total_escrow_frozen: 0,
+            total_frozen_released: 0,
+        };
+        configure(&mut state);
+        let account = program_account(key, &state, VaultState::space());
+        **account.try_borrow_mut_lamports().unwrap() += treasury;
+        Self { authority, platform, battle_program, account }
+    }
+
+    fn state(&self) -> VaultState {
+        load(&self.account)
+    }
+
+    /// battle_core `BattleState` prefix that `BattleSnapshot::load` reads.
+    fn battle(&self, host: Pubkey, status: u8, winner: Option<Pubkey>) -> AccountInfo<'static> {
+        let battle_id = 7u64;
+        let (key, bump) = Pubkey::find_program_address(
+            &[b"battle", self.platform.key.as_ref(), &battle_id.to_le_bytes()],
+            &self.battle_program,
+        );
+        let mut data = hash(b"account:BattleState").to_bytes()[..8].to_vec();
+        self.platform.key().serialize(&mut data).unwrap();
+        battle_id.serialize(&mut data).unwrap();
+        bump.serialize(&mut data).unwrap();
+        host.serialize(&mut data).unwrap();
+        Pubkey::new_unique().serialize(&mut data).unwrap();
+        [None::<Pubkey>, None].serialize(&mut data).unwrap();
+        // Status, turn index and two empty turn submissions.
+        [status, 0, 0, 0].serialize(&mut data).unwrap();
+        winner.serialize(&mut data).unwrap();
+        account_with(key, self.battle_program, SOL, data, false, false)
+    }
+
+    /// Wager on `battle` already locked for `amount`, paying `primary` on settlement.
+    fn wager(
+        &self,
+        battle: &AccountInfo,
+        rent_payer: &AccountInfo,
+        primary: Pubkey,
+        amount: u64,
+        mode: WagerMode,
+    ) -> AccountInfo<'static> {
+        let (key, state) = self.wager_state(battle, rent_payer, primary, amount, mode);
+        program_account(key, &state, WagerState::space())
+    }
+
+    /// Escrow wager on `battle` with both players' `stake` held in its escrow account.
+    fn escrow_wager(
+        &self,
+        battle: &AccountInfo,
+        rent_payer: &AccountInfo,
+        host: Pubkey,
+        challenger: Pubkey,
+        stake: u64,
+    ) -> (AccountInfo<'static>, AccountInfo<'static>) {
+        let (key, mut state) =
+            self.wager_state(battle, rent_payer, host, 2 * stake, WagerMode::Escrow);
+        state.recipient_secondary = Some(challenger);
+        (state.primary_stake, state.secondary_stake) = (stake, stake);
+        let escrow = wallet_at(pda(&[WagerState::ESCROW_SEED, key.as_ref()]).0);
+        **escrow.try_borrow_mut_lamports().unwrap() = 2 * stake;
+        (program_account(key, &state, WagerState::space()), escrow)
+    }
+
+    fn wager_state(
+        &self,
+        battle: &AccountInfo,
+        rent_payer: &AccountInfo,
+        primary: Pubkey,
+        amount: u64,
+        mode: WagerMode,
+    ) -> (Pubkey, WagerState) {
+        let (key, bump) = pda(&[WagerState::SEED, self.account.key.as_ref(), battle.key.as_ref()]);
+        let (_, escrow_bump) = pda(&[WagerState::ESCROW_SEED, key.as_ref()]);
+        let state = WagerState {
+            vault: self.account.key(),
+            battle: battle.key(),
+            bump,
+            locked_amount: amount,
+            recipient_primary: primary,
+            recipient_secondary: None,
+            rent_payer: rent_payer.key(),
+            locked_at: NOW.with(Cell::get),
+            settled_at: None,
+            settled: false,
+            mode,
+            escrow_bump,
+            primary_stake: 0,
+            secondary_stake: 0,
+            mint: None,
+        };
+        (key, state)
+    }
+
+    /// `DenyListEntry` PDA for `wallet`; absent unless the test creates it.
+    fn deny_entry_key(&self, wallet: &Pubkey) -> Pubkey {
+        pda(&[DenyListEntry::SEED, self.account.key.as_ref(), wallet.as_ref()]).0
+    }
+
+    /// Deny list entry for `wallet` holding `frozen_amount` pending review.
+    fn deny_entry(&self, wallet: &Pubkey, frozen_amount: u64) -> AccountInfo<'static> {
+        let (key, bump) = pda(&[DenyListEntry::SEED, self.account.key.as_ref(), wallet.as_ref()]);
+        let entry = DenyListEntry {
+            vault: self.account.key(),
+            wallet: *wallet,
+            bump,
+            reason_code: 1,
+            added_by: self.authority.key(),
+            added_at: START,
+            frozen_amount,
+        };
+        program_account(key, &entry, DenyListEntry::space())
+    }
+}
+
+/// Settles `wager` as battle_core does at the end of a battle, paying the whole pot to
+/// `primary`, whose deny list entry is passed if it has one.
+fn settle(
+    vault: &Vault,
+    wager: &AccountInfo<'static>,
+    rent_payer: &AccountInfo<'static>,
+    battle: AccountInfo<'static>,
+    primary: &AccountInfo<'static>,
+    fee_recipient: Option<&AccountInfo<'static>>,
+    deny_entry: Option<&AccountInfo<'static>>,
+) -> ProgramResult {
+    let amount = load::<WagerState>(wager).locked_amount;
+    process(
+        vec![
+            vault.platform.clone(),
+            vault.account.clone(),
+            wager.clone(),
+            rent_payer.clone(),
+            battle,
+            primary.clone(),
+            none(),
+            none(),
+            system_program_account(),
+            fee_recipient.cloned().unwrap_or_else(none),
+            none(),
+            none(),
+            none(),
+            deny_entry.cloned().unwrap_or_else(|| wallet_at(vault.deny_entry_key(primary.key))),
+            none(),
+        ],
+        instruction::SettlePayout {
+            params: SettlePayoutParams { primary_amount: amount, secondary_amount: None },
+        },
+    )
+}
+
+/// Voids `wager` as battle_core does for a battle that ended without a winner.
+fn void(
+    vault: &Vault,
+    wager: &AccountInfo<'static>,
+    rent_payer: &AccountInfo<'static>,
+    battle: AccountInfo<'static>,
+    stakers: [Option<&AccountInfo<'static>>; 2],
+    escrow: Option<&AccountInfo<'static>>,
+) -> ProgramResult {
+    let optional = |account: Option<&AccountInfo<'static>>| account.cloned().unwrap_or_else(none);
+    process(
+        vec![
+            vault.platform.clone(),
+            vault.account.clone(),
+            wager.clone(),
+            rent_payer.clone(),
+            battle,
+            optional(stakers[0]),
+            optional(stakers[1]),
+            optional(escrow),
+            none(),
+            system_program_account(),
+        ],
+        instruction::VoidWager { reason: VoidReason::BattleCancelled },
+    )
+}
+
+/// Runs the permissionless expiry crank on `wager` from a fresh cranker.
+fn expire(
+    vault: &Vault,
+    wager: &AccountInfo<'static>,
+    rent_payer: &AccountInfo<'static>,
+    battle: AccountInfo<'static>,
+) -> ProgramResult {
+    process(
+        vec![
+            signer(),
+            vault.account.clone(),
+            wager.clone(),
+            rent_payer.clone(),
+            battle,
+            none(),
+            none(),
+            none(),
+            none(),
+            none(),
+        ],
+        instruction::ExpireWager {},
+    )
+}
+
+#[test]
+fn settles_a_house_wager_out_of_the_vault_account() {
+    let stake = SOL / 2;
+    let vault = Vault::new(5 * SOL, |state| state.total_locked = stake);
+    let winner = wallet(0);
+    let rent_payer = wallet(0);
+    let battle = vault.battle(winner.key(), COMPLETED, Some(winner.key()));
+    let wager = vault.wager(&battle, &rent_payer, winner.key(), stake, WagerMode::House);
+    let wager_rent = wager.lamports();
+    let vault_balance = vault.account.lamports();
+
+    settle(&vault, &wager, &rent_payer, battle, &winner, None, None).unwrap();
+
+    assert_eq!(winner.lamports(), stake);
+    assert_eq!(vault.account.lamports(), vault_balance - stake);
+    assert_eq!(rent_payer.lamports(), wager_rent);
+    assert_eq!(wager.lamports(), 0);
+    let state = vault.state();
+    assert_eq!(state.total_locked, 0);
+    assert_eq!(state.total_house_paid_out, stake);
+    assert_eq!(state.total_settled, stake);
+}
+
+#[test]
+fn takes_the_house_fee_out_of_the_vault_account() {
+    let stake = SOL / 2;
+    let fee_recipient = wallet(0);
+    let vault = Vault::new(5 * SOL, |state| {
+        state.total_locked = stake;
+        state.fee_config =
+            FeeConfig { fee_bps: 500, fee_cap: None, fee_recipient: Some(fee_recipient.key()) };
+    });
+    let winner = wallet(0);
+    let rent_payer = wallet(0);
+    let battle = vault.battle(winner.key(), COMPLETED, Some(winner.key()));
+    let wager = vault.wager(&battle, &rent_payer, winner.key(), stake, WagerMode::House);
+    let vault_balance = vault.account.lamports();
+
+    settle(&vault, &wager, &rent_payer, battle, &winner, Some(&fee_recipient), None).unwrap();
+
+    let fee = stake / 20;
+    assert_eq!(fee_recipient.lamports(), fee);
+    assert_eq!(winner.lamports(), stake - fee);
+    assert_eq!(vault.account.lamports(), vault_balance - stake);
+    let state = vault.state();
+    assert_eq!(state.total_fees, fee);
+    assert_eq!(state.total_settled, stake - fee);
+}
+
+#[test]
+fn freezes_a_denied_winners_house_payout_in_the_vault() {
+    let stake = SOL / 2;
+    let vault = Vault::new(5 * SOL, |state| state.total_locked = stake);
+    let winner = wallet(0);
+    let deny_entry = vault.deny_entry(&winner.key(), 0);
+    let rent_payer = wallet(0);
+    let battle = vault.battle(winner.key(), COMPLETED, Some(winner.key()));
+    let wager = vault.wager(&battle, &rent_payer, winner.key(), stake, WagerMode::House);
+    let vault_balance = vault.account.lamports();
+
+    settle(&vault, &wager, &rent_payer, battle, &winner, None, Some(&deny_entry)).unwrap();
+
+    assert_eq!(winner.lamports(), 0);
+    assert_eq!(vault.account.lamports(), vault_balance);
+    assert_eq!(wager.lamports(), 0);
+    assert_eq!(load::<DenyListEntry>(&deny_entry).frozen_amount, stake);
+    let state = vault.state();
+    assert_eq!(state.total_locked, 0);
+    assert_eq!(state.total_frozen, stake);
+    assert_eq!(state.total_house_paid_out, 0);
+}
+
+#[test]
+fn executes_a_queued_withdrawal_once_the_timelock_has_passed() {
+    let delay = 2 * 60 * 60;
+    let treasury = wallet(0);
+    let vault = Vault::new(5 * SOL, |state| {
+        state.withdrawal_policy = WithdrawalPolicy {
+            treasury_destination: Some(treasury.key()),
+            delay_secs: delay,
+            epoch_cap: 2 * SOL,
+        };
+    });
+    let vault_balance = vault.account.lamports();
+    let withdraw = || {
+        process(
+            vec![
+                vault.authority.clone(),
+                vault.account.clone(),
+                treasury.clone(),
+                system_program_account(),
+                none(),
+            ],
+            instruction::EmergencyWithdraw {},
+        )
+    };
+
+    process(
+        vec![vault.authority.clone(), vault.account.clone()],
+        instruction::QueueEmergencyWithdraw { amount: SOL },
+    )
+    .unwrap();
+    assert_eq!(withdraw(), Err(error(VaultError::WithdrawalTimelockActive)));
+
+    advance_clock(delay);
+    withdraw().unwrap();
+
+    assert_eq!(treasury.lamports(), SOL);
+    assert_eq!(vault.account.lamports(), vault_balance - SOL);
+    let state = vault.state();
+    assert_eq!(state.pending_withdrawal, None);
+    assert_eq!(state.total_withdrawn, SOL);
+    assert_eq!(state.epoch_withdrawn, SOL);
+}
+
+#[test]
+fn releases_frozen_funds_out_of_the_vault_account() {
+    let frozen = SOL / 4;
+    let vault = Vault::new(5 * SOL, |state| state.total_frozen = frozen);
+    let denied = wallet(0);
+    let deny_entry = vault.deny_entry(&denied.key(), frozen);
+    let vault_balance = vault.account.lamports();
+
+    process(
+        vec![
+            vault.authority.clone(),
+            vault.account.clone(),
+            deny_entry.clone(),
+            denied.clone(),
+            system_program_account(),
+            none(),
+        ],
+        instruction::ResolveFrozenFunds { resolution: FrozenFundsResolution::Release },
+    )
+    .unwrap();
+
+    assert_eq!(denied.lamports(), frozen);
+    assert_eq!(vault.account.lamports(), vault_balance - frozen);
+    assert_eq!(load::<DenyListEntry>(&deny_entry).frozen_amount, 0);
+    let state = vault.state();
+    assert_eq!(state.total_frozen, 0);
+    assert_eq!(state.total_frozen_released, frozen);
+}
+
+#[test]
+fn expires_a_live_battle_wager_once_the_lock_duration_has_passed() {
+    let stake = SOL / 2;
+    let vault = Vault::new(5 * SOL, |state| state.total_locked = stake);
+    let host = wallet(0);
+    let rent_payer = wallet(0);
+    let battle = vault.battle(host.key(), ACTIVE, None);
+    let wager = vault.wager(&battle, &rent_payer, host.key(), stake, WagerMode::House);
+    let wager_rent = wager.lamports();
+
+    advance_clock(ExpiryPolicy::default().max_lock_duration_secs - 1);
+    assert_eq!(
+        expire(&vault, &wager, &rent_payer, battle.clone()),
+        Err(error(VaultError::WagerNotExpired))
+    );
+    advance_clock(1);
+    expire(&vault, &wager, &rent_payer, battle).unwrap();
+
+    let crank_reward = ExpiryPolicy::default().crank_reward;
+    assert_eq!(rent_payer.lamports(), wager_rent - crank_reward);
+    assert_eq!(wager.lamports(), 0);
+    assert_eq!(vault.state().total_locked, 0);
+}
+
+#[test]
+fn expires_a_wager_at_once_when_its_battle_account_is_closed() {
+    let stake = SOL / 2;
+    let vault = Vault::new(5 * SOL, |state| state.total_locked = stake);
+    let host = wallet(0);
+    let rent_payer = wallet(0);
+    let battle = vault.battle(host.key(), COMPLETED, None);
+    let wager = vault.wager(&battle, &rent_payer, host.key(), stake, WagerMode::House);
+
+    expire(&vault, &wager, &rent_payer, wallet_at(battle.key())).unwrap();
+
+    assert_eq!(wager.lamports(), 0);
+    assert_eq!(vault.state().total_locked, 0);
+}
+
+#[test]
+fn voiding_a_house_wager_releases_its_reservation() {
+    let stake = SOL / 2;
+    let vault = Vault::new(5 * SOL, |state| state.total_locked = stake);
+    let host = wallet(0);
+    let rent_payer = wallet(0);
+    let battle = vault.battle(host.key(), CANCELLED, None);
+    let wager = vault.wager(&battle, &rent_payer, host.key(), stake, WagerMode::House);
+    let wager_rent = wager.lamports();
+    let vault_balance = vault.account.lamports();
+
+    void(&vault, &wager, &rent_payer, battle, [None, None], None).unwrap();
+
+    assert_eq!(host.lamports(), 0);
+    assert_eq!(vault.account.lamports(), vault_balance);
+    assert_eq!(rent_payer.lamports(), wager_rent);
+    assert_eq!(wager.lamports(), 0);
+    let state = vault.state();
+    assert_eq!(state.total_locked, 0);
+    assert_eq!(state.total_settled, 0);
+}
+
+#[test]
+fn voiding_an_escrow_wager_refunds_both_stakes() {
+    let stake = SOL / 4;
+    let vault = Vault::new(5 * SOL, |_| {});
+    let host = wallet(0);
+    let challenger = wallet(0);
+    let rent_payer = wallet(0);
+    let battle = vault.battle(host.key(), CANCELLED, None);
+    let (wager, escrow) =
+        vault.escrow_wager(&battle, &rent_payer, host.key(), challenger.key(), stake);
+    let vault_balance = vault.account.lamports();
+
+    void(&vault, &wager, &rent_payer, battle, [Some(&host), Some(&challenger)], Some(&escrow))
+        .unwrap();
+
+    assert_eq!(host.lamports(), stake);
+    assert_eq!(challenger.lamports(), stake);
+    assert_eq!(escrow.lamports(), 0);
+    assert_eq!(vault.account.lamports(), vault_balance);
+    assert_eq!(wager.lamports(), 0);
+}
+
+#[test]
+fn a_battle_with_a_winner_cannot_be_voided() {
+    let stake = SOL / 2;
+    let vault = Vault::new(5 * SOL, |state| state.total_locked = stake);
+    let host = wallet(0);
+    let rent_payer = wallet(0);
+    let battle = vault.battle(host.key(), COMPLETED, Some(host.key()));
+    let wager = vault.wager(&battle, &rent_payer, host.key(), stake, WagerMode::House);
+
+    assert_eq!(
+        void(&vault, &wager, &rent_payer, battle, [None, None], None),
+        Err(error(VaultError::PayoutNotToWinner))
+    );
+    assert_eq!(vault.state().total_locked, stake);
+}
@@ -3,18 +3,34 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Keypair, PublicKey, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
 import { expect } from "chai";
+import { createHash, randomBytes } from "crypto";
 import { BattleCore } from "../target/types/battle_core";
 
 const PLATFORM_SEED = Buffer.from("platform");
 const PET_SEED = Buffer.from("pet");
 const BATTLE_SEED = Buffer.from("battle");
+const PLAYER_LIMITS_SEED = Buffer.from("player-limits");
+const DENY_LIST_SEED = Buffer.from("deny-list");
+const RANDOMNESS_SEED = Buffer.from("randomness");
+const MOVE_INDEX = { strike: 0, guard: 1, blitz: 2 };
+
+const turnCommitment = (move: keyof typeof MOVE_INDEX, salt: Buffer, player: PublicKey) =>
+  Array.from(
+    createHash("sha256")
+      .update(Buffer.from([MOVE_INDEX[move]]))
+      .update(salt)
+      .update(player.toBuffer())
+      .digest()
+  );
 
 describe("battle_core program", () => {
   const provider = anchor.AnchorProvider.local();
   anchor.setProvider(provider);
 
   const program = anchor.workspace.BattleCore as Program<BattleCore>;
+  const oracle = anchor.workspace.MockRandomnessOracle;
   const host = provider.wallet.publicKey;
+  const [platformPda] = PublicKey.findProgramAddressSync([PLATFORM_SEED], program.programId);
 
   const derivePetPda = (platform: PublicKey, id: number) =>
     PublicKey.findProgramAddressSync(
@@ -28,114 +44,174 @@ describe("battle_core program", () => {
       program.programId
     );
 
-  it("initializes platform, registers pets, runs a deterministic battle resolution", async () => {
-    const [platformPda] = PublicKey.findProgramAddressSync([PLATFORM_SEED], program.programId);
-
-    await program.methods
-      .initializePlatform({ payoutVault: null })
-      .accounts({
-        authority: host,
-        platform: platformPda,
-        systemProgram: SystemProgram.programId,
-      })
-      .rpc();
+  const derivePlayerLimitsPda = (platform: PublicKey, player: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [PLAYER_LIMITS_SEED, platform.toBuffer(), player.toBuffer()],
+      program.programId
+    );
 
-    const platformAccount = await program.account.platformState.fetch(platformPda);
-    expect(platformAccount.authority.toBase58()).to.equal(host.toBase58());
-    expect(platformAccount.nextPetId.toNumber()).to.equal(0);
-    expect(platformAccount.nextBattleId.toNumber()).to.equal(0);
+  // Deny list entries live under the platform's payout vault; this platform has none configured.
+  const deriveDenyEntryPda = (wallet: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [DENY_LIST_SEED, PublicKey.default.toBuffer(), wallet.toBuffer()],
+      anchor.workspace.PayoutVault.programId
+    );
 
-    const hostPetStats = {
-      health: 12,
-      attack: 8,
-      defense: 5,
-      speed: 4,
-    };
+  const deriveRandomnessPda = (battle: PublicKey, round: number) =>
+    PublicKey.findProgramAddressSync(
+      [RANDOMNESS_SEED, battle.toBuffer(), Buffer.from([round])],
+      oracle.programId
+    );
 
-    const [hostPetPda] = derivePetPda(platformPda, 0);
-    await program.methods
-      .registerPet({
-        stats: hostPetStats,
-        metadataUri: "https://example.com/pets/host.json",
-      })
-      .accounts({
-        owner: host,
-        platform: platformPda,
-        pet: hostPetPda,
-        systemProgram: SystemProgram.programId,
-      })
-      .rpc();
+  const hostPetStats = { health: 12, attack: 8, defense: 5, speed: 4 };
+  const challengerPetStats = { health: 10, attack: 5, defense: 6, speed: 3 };
 
-    const challenger = Keypair.generate();
-    const airdropSignature = await provider.connection.requestAirdrop(challenger.publicKey, 2 * LAMPORTS_PER_SOL);
-    await provider.connection.confirmTransaction(airdropSignature, "confirmed");
+  const fundedKeypair = async (lamports = 2 * LAMPORTS_PER_SOL) => {
+    const keypair = Keypair.generate();
+    const signature = await provider.connection.requestAirdrop(keypair.publicKey, lamports);
+    await provider.connection.confirmTransaction(signature, "confirmed");
+    return keypair;
+  };
 
-    const challengerPetStats = {
-      health: 10,
-      attack: 5,
-      defense: 6,
-      speed: 3,
-    };
-    const [challengerPetPda] = derivePetPda(platformPda, 1);
-    await program.methods
-      .registerPet({
-        stats: challengerPetStats,
-        metadataUri: "https://example.com/pets/challenger.json",
-      })
+  // Registers a fresh pet so each test owns its state; `owner` defaults to the provider wallet.
+  const registerPet = async (owner: Keypair | null, stats, tier = { rookie: {} }) => {
+    const { nextPetId } = await program.account.platformState.fetch(platformPda);
+    const [petPda] = derivePetPda(platformPda, nextPetId.toNumber());
+    const builder = program.methods
+      .registerPet({ stats, tier, metadataUri: "https://example.com/pets/spec.json" })
       .accounts({
-        owner: challenger.publicKey,
+        owner: owner?.publicKey ?? host,
         platform: platformPda,
-        pet: challengerPetPda,
+        pet: petPda,
         systemProgram: SystemProgram.programId,
-      })
-      .signers([challenger])
-      .rpc();
+      });
+    await (owner ? builder.signers([owner]) : builder).rpc();
+    return petPda;
+  };
 
-    const [battlePda] = deriveBattlePda(platformPda, 0);
+  // Creates a best-of-one battle hosted by the provider wallet.
+  const createBattle = async (hostPetPda: PublicKey) => {
+    const { nextBattleId } = await program.account.platformState.fetch(platformPda);
+    const [battlePda] = deriveBattlePda(platformPda, nextBattleId.toNumber());
     await program.methods
-      .createBattle({ lockIntent: null })
+      .createBattle({ lockIntent: null, bestOf: 1 })
       .accounts({
         host,
         platform: platformPda,
         hostPet: hostPetPda,
+        hostLimits: derivePlayerLimitsPda(platformPda, host)[0],
+        hostDenyEntry: deriveDenyEntryPda(host)[0],
         battle: battlePda,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
+    return battlePda;
+  };
 
-    await program.methods
+  const joinBattle = (
+    battlePda: PublicKey,
+    hostPetPda: PublicKey,
+    challenger: Keypair,
+    challengerPetPda: PublicKey
+  ) =>
+    program.methods
       .joinBattle()
       .accounts({
         challenger: challenger.publicKey,
         platform: platformPda,
         battle: battlePda,
+        hostPet: hostPetPda,
         challengerPet: challengerPetPda,
+        challengerLimits: derivePlayerLimitsPda(platformPda, challenger.publicKey)[0],
+        challengerDenyEntry: deriveDenyEntryPda(challenger.publicKey)[0],
         systemProgram: SystemProgram.programId,
       })
       .signers([challenger])
       .rpc();
 
-    await program.methods
-      .submitTurn({ moveType: { strike: {} } })
-      .accounts({
-        player: host,
-        platform: platformPda,
-        battle: battlePda,
-        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
-      })
+  const startBattle = async (
+    hostPetPda: PublicKey,
+    challenger: Keypair,
+    challengerPetPda: PublicKey
+  ) => {
+    const battlePda = await createBattle(hostPetPda);
+    await joinBattle(battlePda, hostPetPda, challenger, challengerPetPda);
+    return battlePda;
+  };
+
+  const turnAccounts = (battlePda: PublicKey, player: PublicKey) => ({
+    player,
+    platform: platformPda,
+    battle: battlePda,
+    clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+  });
+
+  const commitMove = async (battlePda: PublicKey, player: Keypair | null, move, salt: Buffer) => {
+    const key = player?.publicKey ?? host;
+    const builder = program.methods
+      .commitTurn({ commitment: turnCommitment(move, salt, key) })
+      .accounts(turnAccounts(battlePda, key));
+    await (player ? builder.signers([player]) : builder).rpc();
+  };
+
+  const revealMove = async (battlePda: PublicKey, player: Keypair | null, move, salt: Buffer) => {
+    const key = player?.publicKey ?? host;
+    const builder = program.methods
+      .revealTurn({ moveType: { [move]: {} }, salt: Array.from(salt) })
+      .accounts(turnAccounts(battlePda, key));
+    await (player ? builder.signers([player]) : builder).rpc();
+  };
+
+  const fulfillRandomness = async (battlePda: PublicKey, round: number, seed: Buffer) => {
+    const [randomnessPda] = deriveRandomnessPda(battlePda, round);
+    await oracle.methods
+      .fulfillRandomness({ battle: battlePda, round, seed: Array.from(seed) })
+      .accounts({ payer: host, randomness: randomnessPda, systemProgram: SystemProgram.programId })
       .rpc();
+    return randomnessPda;
+  };
 
-    await program.methods
-      .submitTurn({ moveType: { guard: {} } })
-      .accounts({
-        player: challenger.publicKey,
-        platform: platformPda,
-        battle: battlePda,
-        clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
-      })
-      .signers([challenger])
+  const setRandomnessOracle = (randomnessOracle: PublicKey | null) =>
+    program.methods
+      .setRandomnessOracle(randomnessOracle)
+      .accounts({ authority: host, platform: platformPda })
       .rpc();
 
+  before(async () => {
+    // The platform is a singleton PDA; other suites in the same validator run may create it.
+    if (!(await program.account.platformState.fetchNullable(platformPda))) {
+      await program.methods
+        .initializePlatform({ payoutVault: null, randomnessOracle: null })
+        .accounts({
+          authority: host,
+          platform: platformPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+  });
+
+  it("initializes platform, registers pets, runs a deterministic battle resolution", async () => {
+    const platformAccount = await program.account.platformState.fetch(platformPda);
+    expect(platformAccount.authority.toBase58()).to.equal(host.toBase58());
+    expect(platformAccount.randomnessOracle).to.be.null;
+
+    const hostPetPda = await registerPet(null, hostPetStats);
+    const challenger = await fundedKeypair();
+    const challengerPetPda = await registerPet(challenger, challengerPetStats);
+    const battlePda = await startBattle(hostPetPda, challenger, challengerPetPda);
+
+    const hostSalt = randomBytes(32);
+    const challengerSalt = randomBytes(32);
+    await commitMove(battlePda, null, "blitz", hostSalt);
+    await commitMove(battlePda, challenger, "guard", challengerSalt);
+
+    const committedBattle = await program.account.battleState.fetch(battlePda);
+    expect(committedBattle.hostSubmission.moveType).to.be.null;
+
+    await revealMove(battlePda, null, "blitz", hostSalt);
+    await revealMove(battlePda, challenger, "guard", challengerSalt);
+
     await program.methods
       .resolveBattle()
       .accounts({
@@ -151,12 +227,152 @@ describe("battle_core program", () => {
     expect("completed" in battleAccount.status).to.be.true;
     expect(battleAccount.winner?.toBase58()).to.equal(host.toBase58());
     expect(battleAccount.resolvedAt).to.not.be.null;
+    expect(battleAccount.roundResults).to.have.lengthOf(1);
+    expect(battleAccount.hostRoundWins).to.equal(1);
 
     const hostPetAccount = await program.account.petState.fetch(hostPetPda);
     const challengerPetAccount = await program.account.petState.fetch(challengerPetPda);
     expect(hostPetAccount.committed).to.be.false;
     expect(challengerPetAccount.committed).to.be.false;
+    expect(hostPetAccount.wins).to.equal(1);
+    expect(challengerPetAccount.losses).to.equal(1);
+    expect(hostPetAccount.rating).to.equal(1220);
+    expect(challengerPetAccount.rating).to.equal(1180);
+  });
+
+  it("rejects a challenger pet from a different tier", async () => {
+    const hostPetPda = await registerPet(null, hostPetStats, { veteran: {} });
+    const challenger = await fundedKeypair();
+    const challengerPetPda = await registerPet(challenger, challengerPetStats);
+    const battlePda = await createBattle(hostPetPda);
+
+    try {
+      await joinBattle(battlePda, hostPetPda, challenger, challengerPetPda);
+      expect.fail("join_battle matched pets from different tiers");
+    } catch (err) {
+      expect(err.error?.errorCode?.code).to.equal("TierMismatch");
+    }
+    const battleAccount = await program.account.battleState.fetch(battlePda);
+    expect("waiting" in battleAccount.status).to.be.true;
+  });
+
+  describe("with the mock randomness oracle", () => {
+    before(() => setRandomnessOracle(oracle.programId));
+    after(() => setRandomnessOracle(null));
+
+    it("resolves a round with randomness fulfilled after both commitments", async () => {
+      const hostPetPda = await registerPet(null, hostPetStats);
+      const challenger = await fundedKeypair();
+      const challengerPetPda = await registerPet(challenger, challengerPetStats);
+      const battlePda = await startBattle(hostPetPda, challenger, challengerPetPda);
+
+      const hostSalt = randomBytes(32);
+      const challengerSalt = randomBytes(32);
+      await commitMove(battlePda, null, "strike", hostSalt);
+      await commitMove(battlePda, challenger, "strike", challengerSalt);
+      const { commitSlot } = await program.account.battleState.fetch(battlePda);
+      while ((await provider.connection.getSlot("confirmed")) <= commitSlot.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 100));
+      }
+      await revealMove(battlePda, null, "strike", hostSalt);
+      await revealMove(battlePda, challenger, "strike", challengerSalt);
+
+      const seed = randomBytes(32);
+      const randomnessPda = await fulfillRandomness(battlePda, 0, seed);
+      const signature = await program.methods
+        .resolveBattle()
+        .accounts({
+          platform: platformPda,
+          battle: battlePda,
+          hostPet: hostPetPda,
+          challengerPet: challengerPetPda,
+          clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+          randomness: randomnessPda,
+        })
+        .rpc({ commitment: "confirmed" });
+
+      const transaction = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const events = [...parser.parseLogs(transaction.meta.logMessages)];
+      const round = events.find((event) => event.name === "RoundResolved");
+      expect(round.data.randomness).to.deep.equal(Array.from(seed));
+
+      const battleAccount = await program.account.battleState.fetch(battlePda);
+      expect("completed" in battleAccount.status).to.be.true;
+      expect(battleAccount.roundResults).to.have.lengthOf(1);
+    });
+
+    it("rejects randomness fulfilled before the last commitment", async () => {
+      const hostPetPda = await registerPet(null, hostPetStats);
+      const challenger = await fundedKeypair();
+      const challengerPetPda = await registerPet(challenger, challengerPetStats);
+      const battlePda = await startBattle(hostPetPda, challenger, challengerPetPda);
+
+      const hostSalt = randomBytes(32);
+      const challengerSalt = randomBytes(32);
+      await commitMove(battlePda, null, "strike", hostSalt);
+      const randomnessPda = await fulfillRandomness(battlePda, 0, randomBytes(32));
+      await commitMove(battlePda, challenger, "guard", challengerSalt);
+      await revealMove(battlePda, null, "strike", hostSalt);
+      await revealMove(battlePda, challenger, "guard", challengerSalt);
+
+      try {
+        await program.methods
+          .resolveBattle()
+          .accounts({
+            platform: platformPda,
+            battle: battlePda,
+            hostPet: hostPetPda,
+            challengerPet: challengerPetPda,
+            clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+            randomness: randomnessPda,
+          })
+          .rpc();
+        expect.fail("resolve_battle accepted randomness fulfilled before the commitments");
+      } catch (err) {
+        expect(err.error?.errorCode?.code).to.equal("RandomnessStale");
+      }
+    });
+  });
+
+  it("lists a pet and sells it to a buyer", async () => {
+    const [platformPda] = PublicKey.findProgramAddressSync([PLATFORM_SEED], program.programId);
+    const [hostPetPda] = derivePetPda(platformPda, 0);
+    const price = new anchor.BN(LAMPORTS_PER_SOL / 10);
+
+    await program.methods
+      .listPet(price)
+      .accounts({ owner: host, platform: platformPda, pet: hostPetPda })
+      .rpc();
+    const listedPet = await program.account.petState.fetch(hostPetPda);
+    expect(listedPet.listingPrice?.toString()).to.equal(price.toString());
+
+    const buyer = Keypair.generate();
+    const airdropSignature = await provider.connection.requestAirdrop(buyer.publicKey, LAMPORTS_PER_SOL);
+    await provider.connection.confirmTransaction(airdropSignature, "confirmed");
+    // The provider wallet pays the transaction fee, so the buyer is debited the price exactly.
+    const buyerBalanceBefore = await provider.connection.getBalance(buyer.publicKey);
+
+    await program.methods
+      .buyPet(price)
+      .accounts({
+        buyer: buyer.publicKey,
+        platform: platformPda,
+        pet: hostPetPda,
+        seller: host,
+        royaltyRecipient: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([buyer])
+      .rpc();
 
-    // TODO(battle_core tests): verify future payout vault CPI hooks and randomness integrations once implemented.
+    const soldPet = await program.account.petState.fetch(hostPetPda);
+    expect(soldPet.owner.toBase58()).to.equal(buyer.publicKey.toBase58());
+    expect(soldPet.listingPrice).to.be.null;
+    const buyerBalanceAfter = await provider.connection.getBalance(buyer.publicKey);
+    expect(buyerBalanceBefore - buyerBalanceAfter).to.equal(price.toNumber());
   });
 });
\ No newline at end of file